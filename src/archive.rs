@@ -0,0 +1,336 @@
+use flate2::read::GzDecoder;
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs::{self, File},
+    io::{self, BufReader, Read},
+    path::{Component, Path, PathBuf},
+    sync::{Arc, Mutex, OnceLock},
+    time::SystemTime,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveKind {
+    Tar,
+    TarGz,
+    Zip,
+}
+
+impl ArchiveKind {
+    // Archives are recognized by their name, like their icons.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_string_lossy().to_lowercase();
+
+        if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(ArchiveKind::TarGz)
+        } else if name.ends_with(".tar") {
+            Some(ArchiveKind::Tar)
+        } else if name.ends_with(".zip") {
+            Some(ArchiveKind::Zip)
+        } else {
+            None
+        }
+    }
+}
+
+// The directories and files of an archive, as paths relative to its root.
+#[derive(Debug)]
+pub struct Archive {
+    kind: ArchiveKind,
+    dirs: BTreeSet<PathBuf>,
+    files: BTreeSet<PathBuf>,
+}
+
+impl Archive {
+    fn read(path: &Path, kind: ArchiveKind) -> io::Result<Self> {
+        let mut archive = Archive {
+            kind,
+            dirs: BTreeSet::new(),
+            files: BTreeSet::new(),
+        };
+
+        match kind {
+            ArchiveKind::Tar | ArchiveKind::TarGz => {
+                let mut tar = tar::Archive::new(tar_reader(path, kind)?);
+                for entry in tar.entries()? {
+                    let entry = entry?;
+                    if let Some(inner) = relative_path(&entry.path()?) {
+                        archive.insert(inner, entry.header().entry_type().is_dir());
+                    }
+                }
+            }
+            ArchiveKind::Zip => {
+                let mut zip = zip::ZipArchive::new(File::open(path)?).map_err(io::Error::other)?;
+                for i in 0..zip.len() {
+                    let file = zip.by_index_raw(i).map_err(io::Error::other)?;
+                    if let Some(inner) = file.enclosed_name().as_deref().and_then(relative_path) {
+                        archive.insert(inner, file.is_dir());
+                    }
+                }
+            }
+        }
+
+        Ok(archive)
+    }
+
+    // Parents are added as well, archives do not need entries for their directories.
+    fn insert(&mut self, inner: PathBuf, is_dir: bool) {
+        for parent in inner.ancestors().skip(1) {
+            if !parent.as_os_str().is_empty() {
+                self.dirs.insert(parent.to_path_buf());
+            }
+        }
+
+        if is_dir {
+            self.dirs.insert(inner);
+        } else {
+            self.files.insert(inner);
+        }
+    }
+
+    // The root of the archive is a directory too.
+    pub fn is_dir(&self, inner: &Path) -> bool {
+        inner.as_os_str().is_empty() || self.dirs.contains(inner)
+    }
+
+    // Children of `inner`, joined to `path`, the path of `inner` as it is browsed.
+    pub fn read_dir(&self, path: &Path, inner: &Path) -> io::Result<(Vec<PathBuf>, Vec<PathBuf>)> {
+        if !self.is_dir(inner) {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "No such directory in the archive",
+            ));
+        }
+
+        let children = |entries: &BTreeSet<PathBuf>| {
+            entries
+                .iter()
+                .filter(|entry| entry.parent() == Some(inner))
+                .filter_map(|entry| entry.file_name())
+                .map(|name| path.join(name))
+                .collect()
+        };
+
+        Ok((children(&self.dirs), children(&self.files)))
+    }
+}
+
+// An archive and the modification time of its file when it was read.
+type Cached = (SystemTime, Arc<Archive>);
+
+// Archives are read once and kept until the file changes.
+pub fn open(path: &Path) -> io::Result<Arc<Archive>> {
+    static ARCHIVES: OnceLock<Mutex<HashMap<PathBuf, Cached>>> = OnceLock::new();
+
+    let kind = ArchiveKind::from_path(path)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Not an archive"))?;
+    let modified = fs::metadata(path)?.modified()?;
+
+    let mut archives = ARCHIVES
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    if let Some((time, archive)) = archives.get(path)
+        && *time == modified
+    {
+        return Ok(Arc::clone(archive));
+    }
+
+    let archive = Arc::new(Archive::read(path, kind)?);
+    archives.insert(path.to_path_buf(), (modified, Arc::clone(&archive)));
+    Ok(archive)
+}
+
+// Writes the directory `inner` of the archive at `path` into `dest`, which must not
+// exist yet. Only directories and regular files are extracted, links are skipped.
+pub fn extract(path: &Path, inner: &Path, dest: &Path) -> io::Result<()> {
+    let kind = open(path)?.kind;
+
+    if dest.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", dest.display()),
+        ));
+    }
+    fs::create_dir_all(dest)?;
+
+    match kind {
+        ArchiveKind::Tar | ArchiveKind::TarGz => {
+            let mut tar = tar::Archive::new(tar_reader(path, kind)?);
+            for entry in tar.entries()? {
+                let mut entry = entry?;
+                let target =
+                    match relative_path(&entry.path()?).and_then(|p| target(&p, inner, dest)) {
+                        Some(t) => t,
+                        None => continue,
+                    };
+
+                let entry_type = entry.header().entry_type();
+                if entry_type.is_dir() {
+                    fs::create_dir_all(&target)?;
+                } else if entry_type.is_file() {
+                    create_parent(&target)?;
+                    entry.unpack(&target)?;
+                }
+            }
+        }
+        ArchiveKind::Zip => {
+            let mut zip = zip::ZipArchive::new(File::open(path)?).map_err(io::Error::other)?;
+            for i in 0..zip.len() {
+                let mut file = zip.by_index(i).map_err(io::Error::other)?;
+                let target = match file
+                    .enclosed_name()
+                    .as_deref()
+                    .and_then(relative_path)
+                    .and_then(|p| target(&p, inner, dest))
+                {
+                    Some(t) => t,
+                    None => continue,
+                };
+
+                if file.is_dir() {
+                    fs::create_dir_all(&target)?;
+                } else if file.is_file() {
+                    create_parent(&target)?;
+                    io::copy(&mut file, &mut File::create(&target)?)?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn tar_reader(path: &Path, kind: ArchiveKind) -> io::Result<Box<dyn Read>> {
+    let file = BufReader::new(File::open(path)?);
+    Ok(match kind {
+        ArchiveKind::TarGz => Box::new(GzDecoder::new(file)),
+        _ => Box::new(file),
+    })
+}
+
+// Entry names without `./`. Absolute names or names with `..` are ignored, they could
+// point outside of the archive.
+fn relative_path(path: &Path) -> Option<PathBuf> {
+    let mut relative = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::Normal(name) => relative.push(name),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+
+    if relative.as_os_str().is_empty() {
+        None
+    } else {
+        Some(relative)
+    }
+}
+
+// Where an entry below `inner` ends up in `dest`, None for other entries.
+fn target(entry: &Path, inner: &Path, dest: &Path) -> Option<PathBuf> {
+    entry.strip_prefix(inner).ok().map(|rest| dest.join(rest))
+}
+
+fn create_parent(path: &Path) -> io::Result<()> {
+    match path.parent() {
+        Some(parent) => fs::create_dir_all(parent),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp_dir::TempDir;
+
+    // A tar with a directory, files and a link, in a fresh directory.
+    fn tar_with_link(name: &str) -> (TempDir, PathBuf) {
+        let dir = TempDir::new(name);
+        let path = dir.path().join("pkg.tar");
+        let mut builder = tar::Builder::new(File::create(&path).unwrap());
+
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Directory);
+        header.set_mode(0o755);
+        header.set_size(0);
+        builder
+            .append_data(&mut header, "pkg/", io::empty())
+            .unwrap();
+
+        for (file, content) in [("pkg/a.txt", "a"), ("pkg/sub/b.txt", "bb")] {
+            let mut header = tar::Header::new_gnu();
+            header.set_mode(0o644);
+            header.set_size(content.len() as u64);
+            builder
+                .append_data(&mut header, file, content.as_bytes())
+                .unwrap();
+        }
+
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Symlink);
+        header.set_size(0);
+        builder
+            .append_link(&mut header, "pkg/link", "/etc/passwd")
+            .unwrap();
+
+        builder.finish().unwrap();
+        (dir, path)
+    }
+
+    #[test]
+    fn relative_path_drops_dots() {
+        assert_eq!(
+            relative_path(Path::new("./pkg/./a.txt")),
+            Some(PathBuf::from("pkg/a.txt"))
+        );
+        assert_eq!(relative_path(Path::new("./")), None);
+    }
+
+    #[test]
+    fn relative_path_rejects_paths_leaving_the_archive() {
+        assert_eq!(relative_path(Path::new("../a.txt")), None);
+        assert_eq!(relative_path(Path::new("pkg/../../a.txt")), None);
+        assert_eq!(relative_path(Path::new("/etc/passwd")), None);
+    }
+
+    #[test]
+    fn read_lists_parents_of_entries() {
+        let (_dir, path) = tar_with_link("read");
+        let archive = open(&path).unwrap();
+
+        assert!(archive.is_dir(Path::new("pkg/sub")));
+        let (dirs, files) = archive.read_dir(&path, Path::new("pkg")).unwrap();
+        assert_eq!(dirs, [path.join("sub")]);
+        assert_eq!(files, [path.join("a.txt"), path.join("link")]);
+    }
+
+    #[test]
+    fn extract_skips_links() {
+        let (_dir, path) = tar_with_link("extract");
+        let dest = path.with_file_name("out");
+
+        extract(&path, Path::new("pkg"), &dest).unwrap();
+        assert_eq!(fs::read_to_string(dest.join("a.txt")).unwrap(), "a");
+        assert_eq!(fs::read_to_string(dest.join("sub/b.txt")).unwrap(), "bb");
+        assert!(fs::symlink_metadata(dest.join("link")).is_err());
+
+        // Existing directories are never written into.
+        assert!(extract(&path, Path::new("pkg"), &dest).is_err());
+    }
+
+    #[test]
+    fn extract_writes_next_to_the_archive() {
+        let (_dir, path) = tar_with_link("dest");
+
+        let dest = crate::dir_util::extract(&path.join("pkg").join("sub")).unwrap();
+        assert_eq!(dest, path.with_file_name("sub"));
+        assert!(dest.join("b.txt").is_file());
+
+        let dest = crate::dir_util::extract(&path).unwrap();
+        assert_eq!(dest, path.with_file_name("pkg"));
+        assert!(dest.join("pkg/a.txt").is_file());
+    }
+}
@@ -0,0 +1,199 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::{
+    env, io,
+    path::Path,
+    process::{Command, ExitStatus, Stdio},
+};
+
+use crate::config::Commands;
+
+#[derive(Debug, Clone, Copy)]
+pub enum CommandKind {
+    Exec,
+    Editor,
+    FileManager,
+    Shell,
+    Pane,
+}
+
+impl CommandKind {
+    pub fn from_key(e: KeyEvent) -> Option<Self> {
+        if !e.modifiers.contains(KeyModifiers::CONTROL) {
+            return None;
+        }
+
+        match e.code {
+            KeyCode::Char('x') => Some(CommandKind::Exec),
+            KeyCode::Char('e') => Some(CommandKind::Editor),
+            KeyCode::Char('o') => Some(CommandKind::FileManager),
+            KeyCode::Char('t') => Some(CommandKind::Shell),
+            KeyCode::Char('w') => Some(CommandKind::Pane),
+            _ => None,
+        }
+    }
+
+    // The shell always starts in the current directory, the rest follow the cursor.
+    pub fn follows_cursor(&self) -> bool {
+        !matches!(self, CommandKind::Shell)
+    }
+
+    // Falls back to the usual environment variables and system openers.
+    pub fn template(&self, commands: &Commands) -> Option<String> {
+        match self {
+            CommandKind::Exec => commands.exec.clone(),
+            CommandKind::Editor => commands.editor.clone().or_else(|| {
+                env::var("VISUAL")
+                    .or_else(|_| env::var("EDITOR"))
+                    .ok()
+                    .map(|editor| format!("{} {{}}", editor))
+            }),
+            CommandKind::FileManager => commands.file_manager.clone().or_else(|| {
+                let opener = if cfg!(target_os = "macos") {
+                    "open"
+                } else {
+                    "xdg-open"
+                };
+                Some(format!("{} {{}}", opener))
+            }),
+            CommandKind::Shell => commands
+                .shell
+                .clone()
+                .or_else(|| env::var("SHELL").ok())
+                .or_else(|| Some(String::from("sh"))),
+            CommandKind::Pane => None,
+        }
+    }
+}
+
+// Runs the template through `sh` inside the directory. The path is handed over as `$1`
+// so it never has to be quoted by hand, templates using `$1` are run as they are. Stdout
+// goes to the terminal, because twiggle's own stdout is usually captured by the shell alias.
+pub fn spawn(template: &str, dir: &Path) -> io::Result<ExitStatus> {
+    let script = if template.contains("$1") {
+        template.to_string()
+    } else {
+        substitute(template)
+    };
+
+    Command::new("sh")
+        .arg("-c")
+        .arg(script)
+        .arg("twiggle")
+        .arg(dir)
+        .current_dir(dir)
+        .stdin(Stdio::inherit())
+        .stdout(Stdio::from(io::stderr()))
+        .stderr(Stdio::inherit())
+        .status()
+}
+
+// Replaces `{}` with `"$1"` where it is a shell word of its own, also when it is quoted
+// like `'{}'`. A `{}` inside other words or quotes, or escaped as `\{\}`, is left alone.
+fn substitute(template: &str) -> String {
+    let mut script = String::new();
+    let mut word = String::new();
+    let mut quote: Option<char> = None;
+    let mut chars = template.chars();
+
+    while let Some(c) = chars.next() {
+        match quote {
+            Some(q) => {
+                word.push(c);
+                if c == q {
+                    quote = None;
+                } else if q == '"' && c == '\\' {
+                    word.extend(chars.next());
+                }
+            }
+            None => match c {
+                '\'' | '"' => {
+                    quote = Some(c);
+                    word.push(c);
+                }
+                '\\' => {
+                    word.push(c);
+                    word.extend(chars.next());
+                }
+                c if c.is_whitespace() || ";&|()<>".contains(c) => {
+                    push_word(&mut script, &mut word);
+                    script.push(c);
+                }
+                c => word.push(c),
+            },
+        }
+    }
+
+    push_word(&mut script, &mut word);
+    script
+}
+
+fn push_word(script: &mut String, word: &mut String) {
+    match word.as_str() {
+        "{}" | "'{}'" | "\"{}\"" => script.push_str("\"$1\""),
+        _ => script.push_str(word),
+    }
+    word.clear();
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::temp_dir::TempDir;
+    use std::fs;
+
+    #[test]
+    fn placeholders_are_whole_words() {
+        assert_eq!(substitute("nvim {}"), "nvim \"$1\"");
+        assert_eq!(substitute("nvim '{}'"), "nvim \"$1\"");
+        assert_eq!(substitute("nvim \"{}\""), "nvim \"$1\"");
+        assert_eq!(
+            substitute("git -C {} status;ls {}|wc"),
+            "git -C \"$1\" status;ls \"$1\"|wc"
+        );
+    }
+
+    #[test]
+    fn other_braces_are_left_alone() {
+        for template in [
+            "echo x{}",
+            "echo 'dir: {}'",
+            "awk '{print}' file",
+            "sh -c 'echo '{}''",
+            "find . -exec cmd \\{\\} \\;",
+        ] {
+            assert_eq!(substitute(template), template);
+        }
+    }
+
+    // Writes what the template printed in `dir` to `out` and returns it.
+    fn run(template: &str) -> (String, String) {
+        let temp = TempDir::new("spawn");
+        let dir = temp.path().join("it's a \"$HOME\" dir");
+        fs::create_dir(&dir).unwrap();
+
+        let status = spawn(template, &dir).unwrap();
+        assert!(status.success());
+        let out = fs::read_to_string(dir.join("out")).unwrap();
+        (dir.to_string_lossy().to_string(), out)
+    }
+
+    #[test]
+    fn directories_with_quotes_and_dollars_arrive_unchanged() {
+        for template in [
+            "printf %s {} > out",
+            "printf %s '{}' > out",
+            "printf %s \"{}\" > out",
+            "printf %s \"$1\" > out",
+        ] {
+            let (dir, out) = run(template);
+            assert_eq!(out, dir, "{}", template);
+        }
+    }
+
+    #[test]
+    fn templates_using_the_argument_are_not_rewritten() {
+        let (_, out) =
+            run("find . -maxdepth 0 -exec printf %s {} \\; > out; printf :%s \"$1\" >> out");
+        assert!(out.starts_with(".:"), "{}", out);
+    }
+}
@@ -0,0 +1,120 @@
+use serde::Deserialize;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct Config {
+    pub commands: Commands,
+    pub multiplexer: Multiplexers,
+    pub theme: Option<String>,
+    // Colors entries like `ls` does if LS_COLORS or EZA_COLORS are set, on by default.
+    pub ls_colors: Option<bool>,
+    // Keys selecting the directories of a page in Select mode, `1234567890` by default.
+    pub select_keys: Option<String>,
+    // Labels every directory in Normal mode, see `hints.rs`.
+    pub hints: Option<bool>,
+    // Keys used for hint labels in Normal and Tree mode, the preferred ones first.
+    pub hint_alphabet: Option<String>,
+    // `substring` (default) or `fuzzy`.
+    pub filter: Option<String>,
+    // Keeps the `/` filter when the directory changes.
+    pub sticky_filter: Option<bool>,
+    // Levels shown in Tree mode, 2 by default.
+    pub tree_depth: Option<usize>,
+    // Files marking a project root for <C-g>, `name:text` also requires the file to contain `text`.
+    pub root_markers: Option<Vec<String>>,
+    // Searched for repositories by <C-a>, `~` and variables are expanded.
+    pub projects_dir: Option<String>,
+    // Levels below `projects_dir` searched for repositories, 3 by default.
+    pub projects_depth: Option<usize>,
+    // Shows badges like Rust or git after project directories, on by default.
+    pub project_badges: Option<bool>,
+    // Lists tar, tar.gz and zip files with the directories to enter them, on by default.
+    pub browse_archives: Option<bool>,
+    pub themes: HashMap<String, ThemeConfig>,
+    pub icons: IconsConfig,
+}
+
+// Command templates, `{}` is replaced by the target directory where it is a word of its
+// own. The directory is also passed as `$1`, templates using `$1` are not rewritten.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct Commands {
+    pub exec: Option<String>,
+    pub editor: Option<String>,
+    pub file_manager: Option<String>,
+    pub shell: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct Multiplexers {
+    pub tmux: MultiplexerCommand,
+    pub zellij: MultiplexerCommand,
+}
+
+// Unset fields fall back to the defaults in `multiplexer.rs`.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct MultiplexerCommand {
+    pub bin: Option<String>,
+    pub args: Option<Vec<String>>,
+}
+
+// Colors are `#rrggbb` or names like `dark_yellow`, unset ones come from `base`.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct ThemeConfig {
+    pub base: Option<String>,
+    pub header_text: Option<String>,
+    pub path: Option<String>,
+    pub history: Option<String>,
+    pub directory: Option<String>,
+    pub directory_group: Option<String>,
+    pub key: Option<String>,
+    pub select: Option<String>,
+    pub file: Option<String>,
+    pub broken_link: Option<String>,
+    pub error: Option<String>,
+    pub debug: Option<String>,
+    pub icon_colors: Option<bool>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct IconsConfig {
+    // Used when `--icons` is given without a set.
+    pub set: Option<String>,
+    pub directories: HashMap<String, String>,
+    pub files: HashMap<String, String>,
+    pub extensions: HashMap<String, String>,
+    pub globs: BTreeMap<String, String>,
+}
+
+impl Config {
+    pub fn load(path: Option<&Path>) -> io::Result<Self> {
+        let path = match path {
+            Some(p) => p.to_path_buf(),
+            None => match default_path() {
+                Some(p) if p.exists() => p,
+                _ => return Ok(Config::default()),
+            },
+        };
+
+        let content = fs::read_to_string(&path)?;
+        toml::from_str(&content).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid config {}: {}", path.display(), e),
+            )
+        })
+    }
+}
+
+fn default_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("twiggle").join("config.toml"))
+}
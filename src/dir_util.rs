@@ -1,14 +1,46 @@
 use std::{
     collections::BTreeMap,
-    env,
+    ffi::OsStr,
     fs::{self, DirEntry},
     io,
     path::{Path, PathBuf},
 };
 
-pub fn get_dirs_files() -> io::Result<(Vec<PathBuf>, Vec<PathBuf>)> {
-    let path = env::current_dir()?;
-    let entries = collect_entries(&path)?;
+use crate::archive::{self, ArchiveKind};
+
+// Where the entries of a path are read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Location {
+    Disk(PathBuf),
+    // `inner` is relative to the root of the archive, and empty for the archive file.
+    Archive { archive: PathBuf, inner: PathBuf },
+}
+
+impl Location {
+    // Decided by the nearest part of `path` that exists on disk: paths below an archive
+    // file are inside the archive, everything else is on disk.
+    pub fn of(path: &Path) -> Self {
+        let existing = path
+            .ancestors()
+            .find(|ancestor| fs::symlink_metadata(ancestor).is_ok());
+
+        match existing {
+            Some(archive) if is_archive(archive) && archive.is_file() => Location::Archive {
+                archive: archive.to_path_buf(),
+                inner: path.strip_prefix(archive).unwrap_or(path).to_path_buf(),
+            },
+            _ => Location::Disk(path.to_path_buf()),
+        }
+    }
+}
+
+// Paths below an archive file are listed from the archive, see `archive.rs`.
+pub fn get_dirs_files(path: &Path) -> io::Result<(Vec<PathBuf>, Vec<PathBuf>)> {
+    if let Location::Archive { archive, inner } = Location::of(path) {
+        return archive::open(&archive)?.read_dir(path, &inner);
+    }
+
+    let entries = collect_entries(path)?;
 
     let mut dirs = Vec::new();
     let mut files = Vec::new();
@@ -24,6 +56,85 @@ pub fn get_dirs_files() -> io::Result<(Vec<PathBuf>, Vec<PathBuf>)> {
     Ok((dirs, files))
 }
 
+// Like `get_dirs_files`, but archive files are listed with the directories if they
+// are browsed.
+pub fn list_dir(path: &Path, browse_archives: bool) -> io::Result<(Vec<PathBuf>, Vec<PathBuf>)> {
+    let (mut dirs, mut files) = get_dirs_files(path)?;
+
+    if browse_archives {
+        let archives;
+        (archives, files) = files
+            .into_iter()
+            .partition(|file| is_archive(file) && file.is_file());
+        dirs.extend(archives);
+        dirs.sort_by(|a, b| a.file_name().cmp(&b.file_name()));
+    }
+
+    Ok((dirs, files))
+}
+
+// The directory a shell can change into for `path`. The shell cannot change into an
+// archive, so paths inside one give the directory containing the archive.
+pub fn shell_dir(path: &Path) -> PathBuf {
+    match Location::of(path) {
+        Location::Archive { archive, .. } => {
+            archive.parent().map(Path::to_path_buf).unwrap_or_default()
+        }
+        Location::Disk(path) => path,
+    }
+}
+
+pub fn is_archive(path: &Path) -> bool {
+    ArchiveKind::from_path(path).is_some()
+}
+
+// Directories on disk and inside archives, including the root of an archive.
+pub fn is_browsable(path: &Path) -> bool {
+    match Location::of(path) {
+        Location::Archive { archive, inner } => is_archive_dir(&archive, &inner),
+        Location::Disk(path) => path.is_dir(),
+    }
+}
+
+fn is_archive_dir(archive: &Path, inner: &Path) -> bool {
+    archive::open(archive).is_ok_and(|archive| archive.is_dir(inner))
+}
+
+// Extracts a directory inside an archive, or the whole archive, next to the archive file.
+// Returns the new directory.
+pub fn extract(path: &Path) -> io::Result<PathBuf> {
+    let (archive_path, inner) = match Location::of(path) {
+        Location::Archive { archive, inner } => (archive, inner),
+        Location::Disk(_) => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Not inside an archive",
+            ));
+        }
+    };
+
+    let name = match inner.file_name() {
+        Some(name) => name.to_os_string(),
+        None => archive_stem(&archive_path).into(),
+    };
+    let dest = archive_path.with_file_name(name);
+
+    archive::extract(&archive_path, &inner, &dest)?;
+    Ok(dest)
+}
+
+// `src.tar.gz` becomes `src`.
+fn archive_stem(path: &Path) -> String {
+    let name = get_name(path);
+    let lower = name.to_lowercase();
+
+    [".tar.gz", ".tgz", ".tar", ".zip"]
+        .iter()
+        .find(|ext| lower.ends_with(*ext))
+        .map(|ext| name[..name.len() - ext.len()].to_string())
+        .unwrap_or(name)
+}
+
 fn collect_entries(path: &Path) -> io::Result<Vec<DirEntry>> {
     let mut entries: Vec<DirEntry> = fs::read_dir(path)?.collect::<Result<_, _>>()?;
     entries.sort_by_key(|entry| entry.file_name());
@@ -34,8 +145,7 @@ pub fn build_char_map(paths: &[PathBuf]) -> BTreeMap<char, Vec<PathBuf>> {
     let mut map: BTreeMap<char, Vec<PathBuf>> = BTreeMap::new();
 
     for path in paths {
-        if let Some(name) = path.file_name() {
-            let c = name.to_string_lossy().chars().next().unwrap();
+        if let Some(c) = get_name(path).chars().next() {
             map.entry(c).or_default().push(path.clone());
         }
     }
@@ -45,11 +155,7 @@ pub fn build_char_map(paths: &[PathBuf]) -> BTreeMap<char, Vec<PathBuf>> {
 
 pub fn starts_with(dirs: &[PathBuf], prefix: &str) -> Vec<PathBuf> {
     dirs.iter()
-        .filter(|dir| {
-            dir.file_name()
-                .map(|os_str| os_str.to_string_lossy().starts_with(prefix))
-                .unwrap_or(false)
-        })
+        .filter(|dir| dir.file_name().is_some() && get_name(dir).starts_with(prefix))
         .cloned()
         .collect()
 }
@@ -58,14 +164,24 @@ pub fn filter_hidden(dirs: &[PathBuf]) -> Vec<PathBuf> {
     dirs.iter()
         .filter(|dir| {
             dir.file_name()
-                .map(|os_str| !os_str.to_string_lossy().starts_with('.'))
+                .map(|os_str| os_str.as_encoded_bytes().first() != Some(&b'.'))
                 .unwrap_or(false)
         })
         .cloned()
         .collect()
 }
 
+// Archive files themselves are not directories, see `is_browsable`.
 pub fn is_directory(path: &Path) -> bool {
+    let (archive, inner) = match Location::of(path) {
+        Location::Archive { archive, inner } => (archive, inner),
+        Location::Disk(path) => return is_disk_directory(&path),
+    };
+
+    !inner.as_os_str().is_empty() && is_archive_dir(&archive, &inner)
+}
+
+fn is_disk_directory(path: &Path) -> bool {
     match fs::symlink_metadata(path) {
         Ok(meta) => {
             if meta.is_dir() {
@@ -83,28 +199,210 @@ pub fn is_directory(path: &Path) -> bool {
         Err(_) => false,
     }
 }
-//
-// pub fn is_directory(path: &Path) -> bool {
-//     path.metadata().map(|meta| meta.is_dir()).unwrap_or(false)
-// }
 
 pub fn is_empty(path: &Path) -> bool {
     if !is_directory(path) {
         return false;
     }
 
-    fs::read_dir(path)
-        .map(|mut entries| entries.next().is_none())
+    match Location::of(path) {
+        Location::Archive { .. } => {
+            get_dirs_files(path).is_ok_and(|(dirs, files)| dirs.is_empty() && files.is_empty())
+        }
+        Location::Disk(path) => {
+            fs::read_dir(path).is_ok_and(|mut entries| entries.next().is_none())
+        }
+    }
+}
+
+pub fn symlink_target(path: &Path) -> Option<PathBuf> {
+    fs::read_link(path).ok()
+}
+
+pub fn is_broken_symlink(path: &Path) -> bool {
+    symlink_target(path).is_some() && fs::metadata(path).is_err()
+}
+
+// Resolves all symlinks, falls back to the logical path if that fails.
+pub fn physical_path(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(unix)]
+pub fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::metadata(path)
+        .map(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
         .unwrap_or(false)
 }
 
+#[cfg(not(unix))]
+pub fn is_executable(_path: &Path) -> bool {
+    false
+}
+
+// Directories inside archives are readable if the archive can be read and has them.
+pub fn is_readable(path: &Path) -> bool {
+    match Location::of(path) {
+        Location::Archive { archive, inner } => is_archive_dir(&archive, &inner),
+        Location::Disk(path) => fs::read_dir(path).is_ok(),
+    }
+}
+
+// Used when the current directory was deleted from under us.
+pub fn nearest_existing_ancestor(path: &Path) -> Option<PathBuf> {
+    path.ancestors()
+        .skip(1)
+        .find(|ancestor| is_browsable(ancestor))
+        .map(|ancestor| ancestor.to_path_buf())
+}
+
 pub fn get_extension(path: &Path) -> Option<String> {
     path.extension()
         .map(|ext| ext.to_string_lossy().to_string())
 }
 
+// The display form of the name, which is also what prefixes are matched against.
 pub fn get_name(path: &Path) -> String {
-    path.file_name()
-        .map(|os| os.to_string_lossy().to_string())
-        .unwrap_or_default()
+    path.file_name().map(escape_os_str).unwrap_or_default()
+}
+
+// Escapes invalid UTF-8 as `\xNN` and control characters like `\n`, so every name is
+// printable. Backslashes are escaped too, so distinct names stay distinct.
+pub fn escape_os_str(s: &OsStr) -> String {
+    let mut escaped = String::new();
+
+    for chunk in s.as_encoded_bytes().utf8_chunks() {
+        for c in chunk.valid().chars() {
+            if c == '\\' || c.is_control() {
+                escaped.extend(c.escape_default());
+            } else {
+                escaped.push(c);
+            }
+        }
+
+        for byte in chunk.invalid() {
+            escaped.push_str(&format!("\\x{:02x}", byte));
+        }
+    }
+
+    escaped
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::temp_dir::TempDir;
+    use std::{fs::File, os::unix::ffi::OsStrExt};
+
+    // `pkg.tar` with `pkg/empty/` and `pkg/a.txt`, next to `file` and the directory `real.zip`.
+    fn tree(name: &str) -> TempDir {
+        let temp = TempDir::new(name);
+        let dir = temp.path();
+        fs::write(dir.join("file"), "").unwrap();
+        fs::create_dir(dir.join("real.zip")).unwrap();
+
+        let mut builder = tar::Builder::new(File::create(dir.join("pkg.tar")).unwrap());
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Directory);
+        header.set_mode(0o755);
+        header.set_size(0);
+        builder
+            .append_data(&mut header, "pkg/empty/", io::empty())
+            .unwrap();
+
+        let mut header = tar::Header::new_gnu();
+        header.set_mode(0o644);
+        header.set_size(1);
+        builder
+            .append_data(&mut header, "pkg/a.txt", "a".as_bytes())
+            .unwrap();
+        builder.finish().unwrap();
+
+        temp
+    }
+
+    #[test]
+    fn locations_follow_the_file_system() {
+        let temp = tree("location");
+        let dir = temp.path();
+        let tar = dir.join("pkg.tar");
+        let inside = |inner: &str| Location::Archive {
+            archive: tar.clone(),
+            inner: PathBuf::from(inner),
+        };
+
+        assert_eq!(Location::of(dir), Location::Disk(dir.to_path_buf()));
+        assert_eq!(Location::of(&tar), inside(""));
+        assert_eq!(Location::of(&tar.join("pkg/empty")), inside("pkg/empty"));
+        assert_eq!(Location::of(&tar.join("missing")), inside("missing"));
+
+        // Only archive files are archives, whatever the names.
+        let real = dir.join("real.zip/x.tar/y");
+        assert_eq!(Location::of(&real), Location::Disk(real.clone()));
+        let missing = dir.join("missing.tar/y");
+        assert_eq!(Location::of(&missing), Location::Disk(missing.clone()));
+    }
+
+    #[test]
+    fn archive_entries_are_checked_in_the_archive() {
+        let temp = tree("predicates");
+        let tar = temp.path().join("pkg.tar");
+
+        assert!(!is_directory(&tar));
+        assert!(is_browsable(&tar));
+        assert!(is_readable(&tar));
+
+        assert!(is_directory(&tar.join("pkg")));
+        assert!(!is_empty(&tar.join("pkg")));
+        assert!(is_empty(&tar.join("pkg/empty")));
+        assert!(is_readable(&tar.join("pkg/empty")));
+
+        for path in [tar.join("pkg/a.txt"), tar.join("missing")] {
+            assert!(!is_directory(&path));
+            assert!(!is_browsable(&path));
+            assert!(!is_readable(&path));
+            assert!(!is_empty(&path));
+        }
+    }
+
+    #[test]
+    fn archives_are_listed_with_the_directories() {
+        let temp = tree("list");
+        let dir = temp.path();
+
+        let (dirs, files) = list_dir(dir, true).unwrap();
+        assert_eq!(dirs, [dir.join("pkg.tar"), dir.join("real.zip")]);
+        assert_eq!(files, [dir.join("file")]);
+
+        let (dirs, files) = list_dir(dir, false).unwrap();
+        assert_eq!(dirs, [dir.join("real.zip")]);
+        assert_eq!(files, [dir.join("file"), dir.join("pkg.tar")]);
+
+        let (dirs, files) = list_dir(&dir.join("pkg.tar/pkg"), true).unwrap();
+        assert_eq!(dirs, [dir.join("pkg.tar/pkg/empty")]);
+        assert_eq!(files, [dir.join("pkg.tar/pkg/a.txt")]);
+    }
+
+    #[test]
+    fn the_shell_gets_the_directory_containing_the_archive() {
+        let temp = tree("shell");
+        let dir = temp.path();
+
+        assert_eq!(shell_dir(dir), dir);
+        assert_eq!(shell_dir(&dir.join("real.zip")), dir.join("real.zip"));
+        assert_eq!(shell_dir(&dir.join("pkg.tar")), dir);
+        assert_eq!(shell_dir(&dir.join("pkg.tar/pkg/empty")), dir);
+    }
+
+    #[test]
+    fn escaped_names_stay_distinct() {
+        let escape = |bytes: &[u8]| escape_os_str(OsStr::from_bytes(bytes));
+
+        assert_eq!(escape(b"a\nb"), r"a\nb");
+        assert_eq!(escape(br"a\nb"), r"a\\nb");
+        assert_eq!(escape(b"\xff"), r"\xff");
+        assert_eq!(escape(br"\xff"), r"\\xff");
+    }
 }
@@ -0,0 +1,155 @@
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
+use std::{collections::VecDeque, io, thread, time::Duration};
+
+pub trait EventSource {
+    // Returns None once there are no more events.
+    fn next_event(&mut self) -> io::Result<Option<Event>>;
+
+    // Waits up to `timeout` for an event, used to redraw while background work runs.
+    fn poll(&mut self, timeout: Duration) -> io::Result<bool>;
+}
+
+pub struct TerminalEvents;
+
+impl EventSource for TerminalEvents {
+    fn next_event(&mut self) -> io::Result<Option<Event>> {
+        Ok(Some(event::read()?))
+    }
+
+    fn poll(&mut self, timeout: Duration) -> io::Result<bool> {
+        event::poll(timeout)
+    }
+}
+
+// Replays keys written in vim notation, e.g. `sr<BS>~<C-s><CR>`.
+pub struct ScriptedEvents {
+    events: VecDeque<Event>,
+}
+
+impl ScriptedEvents {
+    pub fn parse(keys: &str) -> io::Result<Self> {
+        let mut events = VecDeque::new();
+        let mut chars = keys.chars();
+
+        while let Some(c) = chars.next() {
+            if c != '<' {
+                events.push_back(key(KeyCode::Char(c), KeyModifiers::NONE));
+                continue;
+            }
+
+            let mut name = String::new();
+            loop {
+                match chars.next() {
+                    Some('>') => break,
+                    Some(c) => name.push(c),
+                    None => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!("Unclosed key <{}", name),
+                        ));
+                    }
+                }
+            }
+
+            events.push_back(parse_named(&name)?);
+        }
+
+        Ok(ScriptedEvents { events })
+    }
+}
+
+impl EventSource for ScriptedEvents {
+    fn next_event(&mut self) -> io::Result<Option<Event>> {
+        Ok(self.events.pop_front())
+    }
+
+    // Replayed keys wait for background work to finish, so every run ends the same way.
+    fn poll(&mut self, timeout: Duration) -> io::Result<bool> {
+        thread::sleep(timeout);
+        Ok(false)
+    }
+}
+
+fn parse_named(name: &str) -> io::Result<Event> {
+    if let Some(c) = name.strip_prefix("C-").and_then(single_char) {
+        return Ok(key(KeyCode::Char(c), KeyModifiers::CONTROL));
+    }
+
+    let code = match name.to_lowercase().as_str() {
+        "bs" | "backspace" => KeyCode::Backspace,
+        "cr" | "enter" => KeyCode::Enter,
+        "esc" => KeyCode::Esc,
+        "tab" => KeyCode::Tab,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "lt" => KeyCode::Char('<'),
+        "space" => KeyCode::Char(' '),
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Unknown key <{}>", name),
+            ));
+        }
+    };
+
+    Ok(key(code, KeyModifiers::NONE))
+}
+
+fn single_char(s: &str) -> Option<char> {
+    let mut chars = s.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(c),
+        _ => None,
+    }
+}
+
+fn key(code: KeyCode, modifiers: KeyModifiers) -> Event {
+    Event::Key(KeyEvent::new(code, modifiers))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(keys: &str) -> Vec<Event> {
+        let mut events = ScriptedEvents::parse(keys).unwrap();
+        std::iter::from_fn(|| events.next_event().unwrap()).collect()
+    }
+
+    #[test]
+    fn plain_characters_are_keys() {
+        assert_eq!(
+            parse("ab"),
+            vec![
+                key(KeyCode::Char('a'), KeyModifiers::NONE),
+                key(KeyCode::Char('b'), KeyModifiers::NONE),
+            ]
+        );
+    }
+
+    #[test]
+    fn named_keys() {
+        assert_eq!(
+            parse("<CR><Tab><lt>"),
+            vec![
+                key(KeyCode::Enter, KeyModifiers::NONE),
+                key(KeyCode::Tab, KeyModifiers::NONE),
+                key(KeyCode::Char('<'), KeyModifiers::NONE),
+            ]
+        );
+    }
+
+    #[test]
+    fn control_keys() {
+        assert_eq!(
+            parse("<C-x>"),
+            vec![key(KeyCode::Char('x'), KeyModifiers::CONTROL)]
+        );
+    }
+
+    #[test]
+    fn unknown_and_unclosed_keys_are_errors() {
+        assert!(ScriptedEvents::parse("<nope>").is_err());
+        assert!(ScriptedEvents::parse("a<CR").is_err());
+    }
+}
@@ -0,0 +1,62 @@
+use std::path::PathBuf;
+
+use crate::dir_util::get_name;
+
+// How the `/` filter matches names, case is ignored by both.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FilterKind {
+    // The name contains the pattern.
+    #[default]
+    Substring,
+    // The name contains the pattern's characters in order, e.g. `dcs` matches `docs`.
+    Fuzzy,
+}
+
+impl FilterKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "substring" => Some(FilterKind::Substring),
+            "fuzzy" => Some(FilterKind::Fuzzy),
+            _ => None,
+        }
+    }
+
+    pub fn matches(&self, name: &str, pattern: &str) -> bool {
+        let name = name.to_lowercase();
+        let pattern = pattern.to_lowercase();
+
+        match self {
+            FilterKind::Substring => name.contains(&pattern),
+            FilterKind::Fuzzy => {
+                let mut chars = name.chars();
+                pattern.chars().all(|p| chars.any(|c| c == p))
+            }
+        }
+    }
+}
+
+pub fn filter_paths(paths: &[PathBuf], pattern: &str, kind: FilterKind) -> Vec<PathBuf> {
+    paths
+        .iter()
+        .filter(|path| kind.matches(&get_name(path), pattern))
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn substring_ignores_case() {
+        assert!(FilterKind::Substring.matches("Documents", "CUM"));
+        assert!(!FilterKind::Substring.matches("docs", "dcs"));
+    }
+
+    #[test]
+    fn fuzzy_keeps_the_order() {
+        assert!(FilterKind::Fuzzy.matches("docs", "dcs"));
+        assert!(FilterKind::Fuzzy.matches("Docs", "DS"));
+        assert!(!FilterKind::Fuzzy.matches("docs", "sd"));
+    }
+}
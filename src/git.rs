@@ -0,0 +1,71 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+// Porcelain status of one repository, paths are absolute.
+pub struct GitStatus {
+    entries: Vec<(PathBuf, String)>,
+}
+
+impl GitStatus {
+    // Returns None outside of a repository or if git is not installed.
+    pub fn load(dir: &Path) -> Option<Self> {
+        let root = git_output(dir, &["rev-parse", "--show-toplevel"])?;
+        let root = PathBuf::from(root.trim_end());
+
+        let status = git_output(
+            dir,
+            &["status", "--porcelain=v1", "-z", "--ignored=matching", "."],
+        )?;
+
+        let mut entries = Vec::new();
+        let mut records = status.split('\0');
+
+        while let Some(record) = records.next() {
+            if record.len() < 4 {
+                continue;
+            }
+
+            let (code, path) = record.split_at(3);
+            let code = code.trim().to_string();
+
+            // Renames are followed by their source path, which we skip.
+            if code.starts_with('R') || code.starts_with('C') {
+                records.next();
+            }
+
+            entries.push((root.join(path), code));
+        }
+
+        Some(GitStatus { entries })
+    }
+
+    // Directories report the status of their first changed descendant.
+    pub fn status_of(&self, path: &Path) -> Option<&str> {
+        let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+
+        self.entries
+            .iter()
+            .find(|(entry, _)| entry.starts_with(&path))
+            .map(|(_, code)| code.as_str())
+    }
+}
+
+fn git_output(dir: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    String::from_utf8(output.stdout).ok()
+}
@@ -0,0 +1,88 @@
+// Home row first, then the rows above and below.
+pub const DEFAULT_ALPHABET: &str = "asdfghjklqwertyuiopzxcvbnm";
+
+// Unique labels for `n` targets, like vim-easymotion or hop. Every key of the alphabet is
+// a label of its own until there are more targets than keys. Then the last keys become
+// prefixes of two key labels, so no label is the start of another one.
+pub fn hint_labels(n: usize, alphabet: &[char]) -> Vec<String> {
+    if n == 0 || alphabet.len() < 2 {
+        return Vec::new();
+    }
+
+    let mut labels: Vec<String> = alphabet.iter().map(|c| c.to_string()).collect();
+
+    while labels.len() < n {
+        // The least preferred of the shortest labels turns into a prefix.
+        let shortest = labels[0].chars().count();
+        let i = labels
+            .iter()
+            .rposition(|label| label.chars().count() == shortest)
+            .expect("labels are never empty");
+        let prefix = labels.remove(i);
+
+        labels.extend(alphabet.iter().map(|c| format!("{}{}", prefix, c)));
+    }
+
+    labels.truncate(n);
+    labels
+}
+
+// Used for hint alphabets and select keys. They need at least two distinct keys,
+// `~`, `/`, `:` and space are taken by other actions.
+pub fn parse_alphabet(s: &str) -> Result<Vec<char>, String> {
+    let mut alphabet: Vec<char> = Vec::new();
+
+    for c in s.chars() {
+        if matches!(c, '~' | '/' | ':') || c.is_whitespace() {
+            return Err(format!("{:?} cannot be used as a label key", c));
+        }
+        if !alphabet.contains(&c) {
+            alphabet.push(c);
+        }
+    }
+
+    if alphabet.len() < 2 {
+        return Err(String::from("Label keys need at least two distinct keys"));
+    }
+
+    Ok(alphabet)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn single_keys_until_they_run_out() {
+        let alphabet = ['a', 's', 'd'];
+        assert_eq!(hint_labels(2, &alphabet), ["a", "s"]);
+        assert_eq!(hint_labels(4, &alphabet), ["a", "s", "da", "ds"]);
+        assert!(hint_labels(3, &['a']).is_empty());
+    }
+
+    #[test]
+    fn labels_are_unique_and_prefix_free() {
+        let alphabet: Vec<char> = DEFAULT_ALPHABET.chars().collect();
+
+        for n in [0, 1, 26, 27, 100, 700] {
+            let labels = hint_labels(n, &alphabet);
+            assert_eq!(labels.len(), n);
+
+            for (i, a) in labels.iter().enumerate() {
+                for (j, b) in labels.iter().enumerate() {
+                    assert!(i == j || !b.starts_with(a.as_str()), "{} and {}", a, b);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn alphabets_drop_duplicates_and_reject_reserved_keys() {
+        assert_eq!(parse_alphabet("asa"), Ok(vec!['a', 's']));
+        assert!(parse_alphabet("aa").is_err());
+        assert!(parse_alphabet("a~").is_err());
+        assert!(parse_alphabet("a/").is_err());
+        assert!(parse_alphabet("a:").is_err());
+        assert!(parse_alphabet("a s").is_err());
+    }
+}
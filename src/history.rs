@@ -1,10 +1,17 @@
 use std::path::{Path, PathBuf};
 
+#[derive(Clone)]
 pub struct PathHistory {
     pub index: usize,
     pub buffer: Vec<PathBuf>,
 }
 
+impl Default for PathHistory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl PathHistory {
     pub fn new() -> Self {
         PathHistory {
@@ -4,10 +4,16 @@
 // SPDX-FileCopyrightText: 2023-2024 Christina Sørensen, eza contributors
 // SPDX-FileCopyrightText: 2014 Benjamin Sago
 // SPDX-License-Identifier: MIT
+use crossterm::style::{Color, ContentStyle};
 use phf::{Map, phf_map};
-use std::path::Path;
+use std::{collections::HashMap, path::Path};
 
-use crate::dir_util::{get_extension, get_name, is_directory, is_empty};
+use crate::dir_util::{
+    get_extension, get_name, is_broken_symlink, is_directory, is_empty, is_executable, is_readable,
+    symlink_target,
+};
+use crate::ls_colors::LsColors;
+use crate::project::ProjectKind;
 
 #[non_exhaustive]
 struct Icons;
@@ -1107,9 +1113,99 @@ const EXTENSION_ICONS: Map<&'static str, char> = phf_map! {
     "z64"            => '\u{f1393}',             // 󱎓
 };
 
-// Rewritten with PathBuf instead of File for simpler integration.
-pub fn icon_for_file(path: &Path) -> char {
+/// Mapping from full filenames to icon colors, used before `EXTENSION_COLORS`.
+const FILENAME_COLORS: Map<&'static str, Color> = phf_map! {
+    ".gitignore"   => Color::Rgb { r: 0xf5, g: 0x4d, b: 0x27 },
+    "Cargo.lock"   => Color::Rgb { r: 0xde, g: 0xa5, b: 0x84 },
+    "Cargo.toml"   => Color::Rgb { r: 0xde, g: 0xa5, b: 0x84 },
+    "Dockerfile"   => Color::Rgb { r: 0x45, g: 0x8e, b: 0xe6 },
+    "LICENSE"      => Color::Rgb { r: 0xd0, g: 0xbf, b: 0x41 },
+    "Makefile"     => Color::Rgb { r: 0x6d, g: 0x80, b: 0x86 },
+    "README.md"    => Color::Rgb { r: 0xdd, g: 0xdd, b: 0xdd },
+    "package.json" => Color::Rgb { r: 0xe8, g: 0x27, b: 0x4b },
+};
+
+/// Mapping from lowercase file extensions to icon colors, keyed like `EXTENSION_ICONS`.
+const EXTENSION_COLORS: Map<&'static str, Color> = phf_map! {
+    "7z"    => Color::Rgb { r: 0xec, g: 0xa5, b: 0x17 },
+    "bash"  => Color::Rgb { r: 0x89, g: 0xe0, b: 0x51 },
+    "c"     => Color::Rgb { r: 0x59, g: 0x9e, b: 0xff },
+    "cjs"   => Color::Rgb { r: 0xcb, g: 0xcb, b: 0x41 },
+    "cpp"   => Color::Rgb { r: 0xf3, g: 0x4b, b: 0x7d },
+    "css"   => Color::Rgb { r: 0x42, g: 0xa5, b: 0xf5 },
+    "csv"   => Color::Rgb { r: 0x89, g: 0xe0, b: 0x51 },
+    "dart"  => Color::Rgb { r: 0x03, g: 0x58, b: 0x9c },
+    "ex"    => Color::Rgb { r: 0xa0, g: 0x74, b: 0xc4 },
+    "exs"   => Color::Rgb { r: 0xa0, g: 0x74, b: 0xc4 },
+    "fish"  => Color::Rgb { r: 0x4d, g: 0x5a, b: 0x5e },
+    "flac"  => Color::Rgb { r: 0x00, g: 0xaf, b: 0xff },
+    "gif"   => Color::Rgb { r: 0xa0, g: 0x74, b: 0xc4 },
+    "go"    => Color::Rgb { r: 0x00, g: 0xad, b: 0xd8 },
+    "gz"    => Color::Rgb { r: 0xec, g: 0xa5, b: 0x17 },
+    "h"     => Color::Rgb { r: 0xa0, g: 0x74, b: 0xc4 },
+    "hpp"   => Color::Rgb { r: 0xa0, g: 0x74, b: 0xc4 },
+    "hs"    => Color::Rgb { r: 0xa0, g: 0x74, b: 0xc4 },
+    "html"  => Color::Rgb { r: 0xe4, g: 0x4d, b: 0x26 },
+    "java"  => Color::Rgb { r: 0xcc, g: 0x3e, b: 0x44 },
+    "jpeg"  => Color::Rgb { r: 0xa0, g: 0x74, b: 0xc4 },
+    "jpg"   => Color::Rgb { r: 0xa0, g: 0x74, b: 0xc4 },
+    "js"    => Color::Rgb { r: 0xcb, g: 0xcb, b: 0x41 },
+    "json"  => Color::Rgb { r: 0xcb, g: 0xcb, b: 0x41 },
+    "jsx"   => Color::Rgb { r: 0x20, g: 0xc2, b: 0xe3 },
+    "kt"    => Color::Rgb { r: 0x7f, g: 0x52, b: 0xff },
+    "lock"  => Color::Rgb { r: 0xbb, g: 0xbb, b: 0xbb },
+    "lua"   => Color::Rgb { r: 0x51, g: 0xa0, b: 0xcf },
+    "md"    => Color::Rgb { r: 0xdd, g: 0xdd, b: 0xdd },
+    "mjs"   => Color::Rgb { r: 0xcb, g: 0xcb, b: 0x41 },
+    "mkv"   => Color::Rgb { r: 0xfd, g: 0x97, b: 0x1f },
+    "mp3"   => Color::Rgb { r: 0x00, g: 0xaf, b: 0xff },
+    "mp4"   => Color::Rgb { r: 0xfd, g: 0x97, b: 0x1f },
+    "nix"   => Color::Rgb { r: 0x7e, g: 0xba, b: 0xe4 },
+    "pdf"   => Color::Rgb { r: 0xb3, g: 0x0b, b: 0x00 },
+    "php"   => Color::Rgb { r: 0xa0, g: 0x74, b: 0xc4 },
+    "png"   => Color::Rgb { r: 0xa0, g: 0x74, b: 0xc4 },
+    "py"    => Color::Rgb { r: 0x35, g: 0x72, b: 0xa5 },
+    "rb"    => Color::Rgb { r: 0x70, g: 0x15, b: 0x16 },
+    "rs"    => Color::Rgb { r: 0xf7, g: 0x4c, b: 0x00 },
+    "scss"  => Color::Rgb { r: 0xf5, g: 0x53, b: 0x85 },
+    "sh"    => Color::Rgb { r: 0x4d, g: 0x5a, b: 0x5e },
+    "sql"   => Color::Rgb { r: 0xda, g: 0xd8, b: 0xd8 },
+    "svg"   => Color::Rgb { r: 0xff, g: 0xb1, b: 0x3b },
+    "swift" => Color::Rgb { r: 0xe3, g: 0x79, b: 0x33 },
+    "tar"   => Color::Rgb { r: 0xec, g: 0xa5, b: 0x17 },
+    "toml"  => Color::Rgb { r: 0x9c, g: 0x42, b: 0x21 },
+    "ts"    => Color::Rgb { r: 0x51, g: 0x9a, b: 0xba },
+    "tsx"   => Color::Rgb { r: 0x13, g: 0x54, b: 0xbf },
+    "txt"   => Color::Rgb { r: 0x89, g: 0xe0, b: 0x51 },
+    "vim"   => Color::Rgb { r: 0x01, g: 0x98, b: 0x33 },
+    "webp"  => Color::Rgb { r: 0xa0, g: 0x74, b: 0xc4 },
+    "xz"    => Color::Rgb { r: 0xec, g: 0xa5, b: 0x17 },
+    "yaml"  => Color::Rgb { r: 0x6d, g: 0x80, b: 0x86 },
+    "yml"   => Color::Rgb { r: 0x6d, g: 0x80, b: 0x86 },
+    "zig"   => Color::Rgb { r: 0xf6, g: 0x9a, b: 0x1b },
+    "zip"   => Color::Rgb { r: 0xec, g: 0xa5, b: 0x17 },
+    "zsh"   => Color::Rgb { r: 0x89, g: 0xe0, b: 0x51 },
+    "zst"   => Color::Rgb { r: 0xec, g: 0xa5, b: 0x17 },
+};
+
+// Directories are not colored, they keep the color of their row.
+pub fn color_for_file(path: &Path) -> Option<Color> {
     if is_directory(path) {
+        return None;
+    }
+
+    FILENAME_COLORS
+        .get(get_name(path).as_str())
+        .or_else(|| {
+            get_extension(path).and_then(|ext| EXTENSION_COLORS.get(ext.to_lowercase().as_str()))
+        })
+        .copied()
+}
+
+fn glyph_for_file(path: &Path) -> char {
+    if is_directory(path) && !is_readable(path) {
+        Icons::LOCK // 
+    } else if is_directory(path) {
         *DIRECTORY_ICONS
             .get(get_name(path).as_str())
             .unwrap_or_else(|| {
@@ -1127,3 +1223,249 @@ pub fn icon_for_file(path: &Path) -> char {
         Icons::FILE_UNKNOW // 󰡯
     }
 }
+
+// Badges shown after project directories, in the colors of their languages.
+pub fn project_icon(kind: ProjectKind) -> (char, Option<Color>) {
+    let (glyph, color) = match kind {
+        ProjectKind::Rust => (Icons::LANG_RUST, EXTENSION_COLORS.get("rs")),
+        ProjectKind::Node => (Icons::NPM, FILENAME_COLORS.get("package.json")),
+        ProjectKind::Python => (Icons::LANG_PYTHON, EXTENSION_COLORS.get("py")),
+        ProjectKind::Go => (Icons::LANG_GO, EXTENSION_COLORS.get("go")),
+        ProjectKind::Git => (Icons::GIT, FILENAME_COLORS.get(".gitignore")),
+    };
+    (glyph, color.copied())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IconSet {
+    Nerd,
+    Unicode,
+    Ascii,
+}
+
+impl IconSet {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "nerd" => Some(IconSet::Nerd),
+            "unicode" => Some(IconSet::Unicode),
+            "ascii" => Some(IconSet::Ascii),
+            _ => None,
+        }
+    }
+}
+
+// User icons, they win over the icon set. Globs only support `*` and `?`.
+#[derive(Debug, Clone, Default)]
+pub struct IconOverrides {
+    pub directories: HashMap<String, String>,
+    pub files: HashMap<String, String>,
+    pub extensions: HashMap<String, String>,
+    pub globs: Vec<(String, String)>,
+}
+
+pub struct IconProvider {
+    set: IconSet,
+    overrides: IconOverrides,
+}
+
+impl IconProvider {
+    pub fn new(set: IconSet, mut overrides: IconOverrides) -> Self {
+        // Longer patterns are usually more specific, so they are tried first.
+        overrides
+            .globs
+            .sort_by_key(|(pattern, _)| std::cmp::Reverse(pattern.len()));
+        IconProvider { set, overrides }
+    }
+
+    // User icons replace the glyph, the color still follows the file type.
+    pub fn icon(&self, path: &Path) -> (String, Option<Color>) {
+        (self.glyph(path), color_for_file(path))
+    }
+
+    fn glyph(&self, path: &Path) -> String {
+        if is_directory(path) && !is_readable(path) {
+            return self.set_icon(path);
+        }
+
+        let name = get_name(path);
+        let names = if is_directory(path) {
+            &self.overrides.directories
+        } else {
+            &self.overrides.files
+        };
+
+        if let Some(icon) = names.get(&name) {
+            return icon.clone();
+        }
+
+        if let Some((_, icon)) = self
+            .overrides
+            .globs
+            .iter()
+            .find(|(pattern, _)| glob_match(pattern, &name))
+        {
+            return icon.clone();
+        }
+
+        if !is_directory(path)
+            && let Some(icon) = get_extension(path)
+                .and_then(|ext| self.overrides.extensions.get(&ext.to_lowercase()))
+        {
+            return icon.clone();
+        }
+
+        self.set_icon(path)
+    }
+
+    // Shown in front of rows with several directories.
+    pub fn group_icon(&self) -> &'static str {
+        match self.set {
+            IconSet::Nerd => "󰉓",
+            IconSet::Unicode => "▸",
+            IconSet::Ascii => "/",
+        }
+    }
+
+    // The nerd glyph of the project, other sets have no fitting symbols and use its name.
+    pub fn project_badge(&self, kind: ProjectKind) -> (String, Option<Color>) {
+        match self.set {
+            IconSet::Nerd => {
+                let (glyph, color) = project_icon(kind);
+                (glyph.to_string(), color)
+            }
+            IconSet::Unicode | IconSet::Ascii => (kind.name().to_string(), None),
+        }
+    }
+
+    fn set_icon(&self, path: &Path) -> String {
+        match self.set {
+            IconSet::Nerd => glyph_for_file(path).to_string(),
+            IconSet::Unicode => unicode_icon(path).to_string(),
+            IconSet::Ascii => ascii_icon(path).to_string(),
+        }
+    }
+}
+
+// Symbols with a width of one column that every common font has.
+fn unicode_icon(path: &Path) -> char {
+    if is_broken_symlink(path) {
+        '✗'
+    } else if symlink_target(path).is_some() {
+        '↪'
+    } else if is_directory(path) && !is_readable(path) {
+        '⊘'
+    } else if is_directory(path) {
+        if is_empty(path) { '▹' } else { '▸' }
+    } else if is_executable(path) {
+        '⚙'
+    } else {
+        match get_extension(path).map(|ext| ext.to_lowercase()).as_deref() {
+            Some("zip" | "tar" | "gz" | "xz" | "bz2" | "zst" | "7z" | "rar") => '▤',
+            Some("png" | "jpg" | "jpeg" | "gif" | "svg" | "webp" | "bmp") => '▣',
+            Some("mp3" | "flac" | "ogg" | "wav" | "m4a" | "opus") => '♪',
+            Some("mp4" | "mkv" | "webm" | "avi" | "mov") => '►',
+            Some("md" | "txt" | "pdf" | "doc" | "docx" | "odt" | "rst") => '¶',
+            _ => '•',
+        }
+    }
+}
+
+// Markers in the style of `ls -F`.
+fn ascii_icon(path: &Path) -> char {
+    if is_broken_symlink(path) {
+        '!'
+    } else if symlink_target(path).is_some() {
+        '@'
+    } else if is_directory(path) {
+        '/'
+    } else if is_executable(path) {
+        '*'
+    } else {
+        '-'
+    }
+}
+
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+
+    // Classic backtracking over the last `*`.
+    let (mut p, mut n) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = star {
+            p = star_p + 1;
+            n = star_n + 1;
+            star = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+// Same classification as `IconProvider::icon`, but with the styles from LS_COLORS.
+pub fn style_for_file(path: &Path, ls_colors: &LsColors) -> Option<ContentStyle> {
+    if is_broken_symlink(path) {
+        ls_colors
+            .for_type("or")
+            .or_else(|| ls_colors.for_type("ln"))
+    } else if symlink_target(path).is_some() {
+        ls_colors.for_type("ln")
+    } else if is_directory(path) {
+        ls_colors.for_type("di")
+    } else if let Some(style) = ls_colors.for_name(&get_name(path)) {
+        Some(style)
+    } else if is_executable(path) {
+        ls_colors.for_type("ex")
+    } else {
+        ls_colors.for_type("fi")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob_matching() {
+        let cases = [
+            ("*.test.js", "app.test.js", true),
+            ("*.test.js", "app.js", false),
+            ("*", "anything", true),
+            ("*", "", true),
+            ("a*b*c", "aXbYbZc", true),
+            ("a*b*c", "aXbYcZ", false),
+            ("?", "x", true),
+            ("?", "", false),
+            ("??.rs", "ab.rs", true),
+            ("??.rs", "abc.rs", false),
+            ("file?.txt", "filé1.txt", false),
+            ("fil?", "filé", true),
+            // Unlike shells, `*` and `?` match a leading dot.
+            ("*rc", ".bashrc", true),
+            ("?env", ".env", true),
+            (".*", ".gitignore", true),
+            (".*", "gitignore", false),
+            // Matching is case sensitive.
+            ("*.JPG", "photo.jpg", false),
+            ("Makefile*", "Makefile.am", true),
+            ("makefile*", "Makefile.am", false),
+            // The empty pattern only matches the empty name.
+            ("", "", true),
+            ("", "a", false),
+        ];
+
+        for (pattern, name, expected) in cases {
+            assert_eq!(glob_match(pattern, name), expected, "{} {}", pattern, name);
+        }
+    }
+}
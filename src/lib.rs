@@ -0,0 +1,19 @@
+pub mod archive;
+pub mod dir_util;
+pub mod event_source;
+pub mod filter;
+pub mod git;
+pub mod hints;
+pub mod history;
+pub mod icons;
+pub mod ls_colors;
+pub mod metadata;
+pub mod mode;
+pub mod navigator;
+pub mod paging;
+pub mod project;
+pub mod prompt;
+#[cfg(test)]
+mod temp_dir;
+pub mod tree;
+pub mod usage;
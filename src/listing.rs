@@ -0,0 +1,245 @@
+use serde::Serialize;
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use twiggle::dir_util::{build_char_map, escape_os_str, get_name, is_directory};
+use twiggle::git::GitStatus;
+use twiggle::hints::hint_labels;
+use twiggle::icons::IconProvider;
+use twiggle::paging::Paging;
+
+#[derive(Serialize)]
+struct Listing {
+    path: String,
+    directories: Vec<Entry>,
+    files: Vec<Entry>,
+}
+
+#[derive(Serialize)]
+struct Entry {
+    name: String,
+    path: String,
+    key: Option<String>,
+    icon: String,
+    hidden: bool,
+    size: Option<u64>,
+    git: Option<String>,
+}
+
+pub enum Format {
+    Json,
+    Tsv,
+}
+
+// Prints what the interactive view would show, without touching the terminal.
+pub fn print(
+    format: Format,
+    current_dir: &Path,
+    dirs: &[PathBuf],
+    files: &[PathBuf],
+    keybinds: &str,
+    hint_alphabet: Option<&[char]>,
+    icons: &IconProvider,
+) -> io::Result<()> {
+    let listing = listing(current_dir, dirs, files, keybinds, hint_alphabet, icons);
+
+    // A closed pipe, like `--json | head`, only means nobody reads the rest.
+    match write(format, &listing, &mut io::stdout().lock()) {
+        Err(err) if err.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        result => result,
+    }
+}
+
+fn listing(
+    current_dir: &Path,
+    dirs: &[PathBuf],
+    files: &[PathBuf],
+    keybinds: &str,
+    hint_alphabet: Option<&[char]>,
+    icons: &IconProvider,
+) -> Listing {
+    let git = GitStatus::load(current_dir);
+
+    let mut directories = Vec::new();
+
+    // With hints every directory has its own label, in the order of the listing.
+    if let Some(alphabet) = hint_alphabet {
+        let labels = hint_labels(dirs.len(), alphabet);
+        for (dir, label) in dirs.iter().zip(labels) {
+            directories.push(entry(dir, Some(label), icons, git.as_ref()));
+        }
+    } else {
+        // Groups larger than a page need <C-f> to reach the later pages, like in Select mode.
+        let keys: Vec<char> = keybinds.chars().collect();
+        let paging = Paging::new(keys.len(), None);
+
+        for (c, group) in &build_char_map(dirs) {
+            for (i, dir) in group.iter().enumerate() {
+                let key = if group.len() == 1 {
+                    c.to_string()
+                } else {
+                    let page = i / paging.size();
+                    let key = keys[i % paging.size()];
+                    format!("{}{}{}", c, "<C-f>".repeat(page), key)
+                };
+                directories.push(entry(dir, Some(key), icons, git.as_ref()));
+            }
+        }
+    }
+
+    let files = files
+        .iter()
+        .map(|file| entry(file, None, icons, git.as_ref()))
+        .collect::<Vec<Entry>>();
+
+    Listing {
+        path: escape_os_str(current_dir.as_os_str()),
+        directories,
+        files,
+    }
+}
+
+fn write(format: Format, listing: &Listing, out: &mut impl Write) -> io::Result<()> {
+    match format {
+        Format::Json => {
+            serde_json::to_writer_pretty(&mut *out, listing)?;
+            writeln!(out)?;
+        }
+        Format::Tsv => {
+            for (kind, entries) in [("dir", &listing.directories), ("file", &listing.files)] {
+                for e in entries {
+                    writeln!(
+                        out,
+                        "{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                        kind,
+                        e.key.as_deref().unwrap_or(""),
+                        e.icon,
+                        e.name,
+                        e.hidden,
+                        e.size.map(|s| s.to_string()).unwrap_or_default(),
+                        e.git.as_deref().unwrap_or(""),
+                    )?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn entry(path: &Path, key: Option<String>, icons: &IconProvider, git: Option<&GitStatus>) -> Entry {
+    let name = get_name(path);
+    let size = if is_directory(path) {
+        None
+    } else {
+        fs::symlink_metadata(path).ok().map(|meta| meta.len())
+    };
+
+    Entry {
+        hidden: name.starts_with('.'),
+        path: escape_os_str(path.as_os_str()),
+        key,
+        icon: icons.icon(path).0,
+        size,
+        git: git.and_then(|g| g.status_of(path)).map(String::from),
+        name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp_dir::TempDir;
+    use twiggle::icons::{IconOverrides, IconSet};
+
+    // The `--list` output for `dirs` and `files` created in a fresh directory.
+    fn list(dirs: &[&str], files: &[&str], keybinds: &str, hints: Option<&[char]>) -> String {
+        let temp = TempDir::new("listing");
+        let dir = temp.path();
+        for name in dirs {
+            fs::create_dir(dir.join(name)).unwrap();
+        }
+        for name in files {
+            fs::write(dir.join(name), "four").unwrap();
+        }
+
+        let (dirs, files) = twiggle::dir_util::get_dirs_files(dir).unwrap();
+        let icons = IconProvider::new(IconSet::Ascii, IconOverrides::default());
+        let listing = listing(dir, &dirs, &files, keybinds, hints, &icons);
+
+        let mut out = Vec::new();
+        write(Format::Tsv, &listing, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn plain() {
+        assert_eq!(
+            list(&["alpha", ".hidden"], &["notes.txt"], "12", None),
+            "dir\t.\t/\t.hidden\ttrue\t\t\n\
+             dir\ta\t/\talpha\tfalse\t\t\n\
+             file\t\t-\tnotes.txt\tfalse\t4\t\n"
+        );
+    }
+
+    #[test]
+    fn grouped() {
+        assert_eq!(
+            list(&["bar", "baz", "cat"], &[], "12", None),
+            "dir\tb1\t/\tbar\tfalse\t\t\n\
+             dir\tb2\t/\tbaz\tfalse\t\t\n\
+             dir\tc\t/\tcat\tfalse\t\t\n"
+        );
+    }
+
+    #[test]
+    fn overflowing_groups_page_like_select_mode() {
+        assert_eq!(
+            list(&["b1", "b2", "b3", "b4", "b5"], &[], "12", None),
+            "dir\tb1\t/\tb1\tfalse\t\t\n\
+             dir\tb2\t/\tb2\tfalse\t\t\n\
+             dir\tb<C-f>1\t/\tb3\tfalse\t\t\n\
+             dir\tb<C-f>2\t/\tb4\tfalse\t\t\n\
+             dir\tb<C-f><C-f>1\t/\tb5\tfalse\t\t\n"
+        );
+    }
+
+    #[test]
+    fn hint_labels() {
+        assert_eq!(
+            list(&["bar", "baz", "cat"], &[], "12", Some(&['a', 's'])),
+            "dir\ta\t/\tbar\tfalse\t\t\n\
+             dir\tsa\t/\tbaz\tfalse\t\t\n\
+             dir\tss\t/\tcat\tfalse\t\t\n"
+        );
+    }
+
+    #[test]
+    fn json_has_the_same_fields() {
+        let temp = TempDir::new("listing-json");
+        fs::create_dir(temp.path().join("alpha")).unwrap();
+        let dirs = vec![temp.path().join("alpha")];
+        let icons = IconProvider::new(IconSet::Ascii, IconOverrides::default());
+        let listing = listing(temp.path(), &dirs, &[], "12", None, &icons);
+
+        let mut out = Vec::new();
+        write(Format::Json, &listing, &mut out).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(
+            json["directories"][0],
+            serde_json::json!({
+                "name": "alpha",
+                "path": escape_os_str(dirs[0].as_os_str()),
+                "key": "a",
+                "icon": "/",
+                "hidden": false,
+                "size": null,
+                "git": null,
+            })
+        );
+        assert_eq!(json["files"], serde_json::json!([]));
+    }
+}
@@ -0,0 +1,261 @@
+use crossterm::style::{Attribute, Color, ContentStyle};
+use std::{collections::HashMap, env};
+
+// Entry styles from `LS_COLORS` and `EZA_COLORS`, e.g. `di=01;34:*.rs=38;5;208`.
+#[derive(Debug, Clone, Default)]
+pub struct LsColors {
+    types: HashMap<String, ContentStyle>,
+    // Patterns like `*.tar.gz` or `*README`, stored lowercase without the `*`.
+    suffixes: Vec<(String, ContentStyle)>,
+}
+
+impl LsColors {
+    pub fn from_env() -> Option<Self> {
+        LsColors::from_vars(
+            env::var("LS_COLORS").ok().as_deref(),
+            env::var("EZA_COLORS").ok().as_deref(),
+        )
+    }
+
+    // EZA_COLORS is applied on top of LS_COLORS, unless it has a `reset` entry.
+    // Empty variables count as unset.
+    pub fn from_vars(ls_colors: Option<&str>, eza_colors: Option<&str>) -> Option<Self> {
+        let ls_colors = ls_colors.filter(|s| !s.is_empty());
+        let eza_colors = eza_colors.filter(|s| !s.is_empty());
+
+        if ls_colors.is_none() && eza_colors.is_none() {
+            return None;
+        }
+
+        let mut colors = LsColors::default();
+
+        let reset = eza_colors.is_some_and(|s| s.split(':').any(|entry| entry == "reset"));
+
+        if let Some(ls_colors) = ls_colors
+            && !reset
+        {
+            colors.extend(ls_colors);
+        }
+
+        if let Some(eza_colors) = eza_colors {
+            colors.extend(eza_colors);
+        }
+
+        Some(colors)
+    }
+
+    pub fn parse(s: &str) -> Self {
+        let mut colors = LsColors::default();
+        colors.extend(s);
+        colors
+    }
+
+    fn extend(&mut self, s: &str) {
+        for entry in s.split(':') {
+            let (key, codes) = match entry.split_once('=') {
+                Some(pair) => pair,
+                None => continue,
+            };
+
+            let style = parse_sgr(codes);
+
+            match key.strip_prefix('*') {
+                Some(suffix) => {
+                    let suffix = suffix.to_lowercase();
+                    self.suffixes.retain(|(s, _)| s != &suffix);
+                    self.suffixes.push((suffix, style));
+                }
+                None => {
+                    self.types.insert(key.to_string(), style);
+                }
+            }
+        }
+    }
+
+    // Two letter keys like `di`, `ln`, `or`, `ex` and `fi`.
+    pub fn for_type(&self, key: &str) -> Option<ContentStyle> {
+        self.types.get(key).copied()
+    }
+
+    // The longest matching suffix wins, so `*.tar.gz` beats `*.gz`.
+    pub fn for_name(&self, name: &str) -> Option<ContentStyle> {
+        let name = name.to_lowercase();
+
+        self.suffixes
+            .iter()
+            .filter(|(suffix, _)| name.ends_with(suffix.as_str()))
+            .max_by_key(|(suffix, _)| suffix.len())
+            .map(|(_, style)| *style)
+    }
+}
+
+// Parses SGR parameters like `01;38;5;208`, unknown codes are skipped.
+pub fn parse_sgr(codes: &str) -> ContentStyle {
+    let mut style = ContentStyle::new();
+    let mut numbers = codes.split(';').filter_map(|n| n.parse::<u8>().ok());
+
+    while let Some(n) = numbers.next() {
+        match n {
+            0 => style = ContentStyle::new(),
+            1 => style.attributes.set(Attribute::Bold),
+            2 => style.attributes.set(Attribute::Dim),
+            3 => style.attributes.set(Attribute::Italic),
+            4 => style.attributes.set(Attribute::Underlined),
+            5 => style.attributes.set(Attribute::SlowBlink),
+            7 => style.attributes.set(Attribute::Reverse),
+            30..=37 => style.foreground_color = Some(ansi_color(n - 30)),
+            38 => style.foreground_color = extended_color(&mut numbers),
+            39 => style.foreground_color = None,
+            40..=47 => style.background_color = Some(ansi_color(n - 40)),
+            48 => style.background_color = extended_color(&mut numbers),
+            49 => style.background_color = None,
+            90..=97 => style.foreground_color = Some(ansi_color(n - 90 + 8)),
+            100..=107 => style.background_color = Some(ansi_color(n - 100 + 8)),
+            _ => {}
+        }
+    }
+
+    style
+}
+
+fn extended_color(numbers: &mut impl Iterator<Item = u8>) -> Option<Color> {
+    match numbers.next()? {
+        5 => numbers.next().map(Color::AnsiValue),
+        2 => Some(Color::Rgb {
+            r: numbers.next()?,
+            g: numbers.next()?,
+            b: numbers.next()?,
+        }),
+        _ => None,
+    }
+}
+
+fn ansi_color(n: u8) -> Color {
+    match n {
+        0 => Color::Black,
+        1 => Color::DarkRed,
+        2 => Color::DarkGreen,
+        3 => Color::DarkYellow,
+        4 => Color::DarkBlue,
+        5 => Color::DarkMagenta,
+        6 => Color::DarkCyan,
+        7 => Color::Grey,
+        8 => Color::DarkGrey,
+        9 => Color::Red,
+        10 => Color::Green,
+        11 => Color::Yellow,
+        12 => Color::Blue,
+        13 => Color::Magenta,
+        14 => Color::Cyan,
+        _ => Color::White,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn style(foreground: Option<Color>, attributes: &[Attribute]) -> ContentStyle {
+        let mut style = ContentStyle::new();
+        style.foreground_color = foreground;
+        for attribute in attributes {
+            style.attributes.set(*attribute);
+        }
+        style
+    }
+
+    #[test]
+    fn parses_sgr_codes() {
+        let cases = [
+            ("", style(None, &[])),
+            ("01;34", style(Some(Color::DarkBlue), &[Attribute::Bold])),
+            ("1;94", style(Some(Color::Blue), &[Attribute::Bold])),
+            ("38;5;208", style(Some(Color::AnsiValue(208)), &[])),
+            (
+                "38;2;206;66;43",
+                style(
+                    Some(Color::Rgb {
+                        r: 206,
+                        g: 66,
+                        b: 43,
+                    }),
+                    &[],
+                ),
+            ),
+            ("04;33;39", style(None, &[Attribute::Underlined])),
+            // 0 resets everything before it.
+            ("01;31;0;32", style(Some(Color::DarkGreen), &[])),
+            ("0", style(None, &[])),
+            // Unknown and malformed codes are skipped.
+            ("x;999;31", style(Some(Color::DarkRed), &[])),
+            ("38;5", style(None, &[])),
+        ];
+
+        for (codes, expected) in cases {
+            assert_eq!(parse_sgr(codes), expected, "{}", codes);
+        }
+    }
+
+    #[test]
+    fn parses_backgrounds() {
+        let style = parse_sgr("30;42");
+        assert_eq!(style.foreground_color, Some(Color::Black));
+        assert_eq!(style.background_color, Some(Color::DarkGreen));
+        assert_eq!(parse_sgr("48;5;1;49").background_color, None);
+    }
+
+    #[test]
+    fn types_and_longest_suffixes() {
+        let colors = LsColors::parse("rs=0:di=01;34:*.gz=31:*.tar.gz=32:*README=33:bogus");
+
+        assert_eq!(colors.for_type("rs"), Some(ContentStyle::new()));
+        assert_eq!(colors.for_type("di"), Some(parse_sgr("01;34")));
+        assert_eq!(colors.for_type("ln"), None);
+        assert_eq!(colors.for_name("a.GZ"), Some(parse_sgr("31")));
+        assert_eq!(colors.for_name("a.tar.gz"), Some(parse_sgr("32")));
+        assert_eq!(colors.for_name("README"), Some(parse_sgr("33")));
+        assert_eq!(colors.for_name("a.rs"), None);
+    }
+
+    #[test]
+    fn eza_colors_override_ls_colors() {
+        let cases = [
+            (None, None, None, None),
+            (Some(""), Some(""), None, None),
+            (Some("di=34:*.rs=31"), None, Some("34"), Some("31")),
+            (None, Some("di=35"), Some("35"), None),
+            (Some("di=34:*.rs=31"), Some("di=35"), Some("35"), Some("31")),
+            (
+                Some("di=34:*.rs=31"),
+                Some("*.RS=32"),
+                Some("34"),
+                Some("32"),
+            ),
+            (Some("di=34:*.rs=31"), Some("reset:di=35"), Some("35"), None),
+            (Some("di=34"), Some("reset"), None, None),
+        ];
+
+        for (ls, eza, di, rs) in cases {
+            let colors = LsColors::from_vars(ls, eza);
+            let expected = |codes: Option<&str>| codes.map(parse_sgr);
+
+            assert_eq!(
+                colors.as_ref().and_then(|c| c.for_type("di")),
+                expected(di),
+                "{:?} {:?}",
+                ls,
+                eza
+            );
+            assert_eq!(
+                colors.as_ref().and_then(|c| c.for_name("main.rs")),
+                expected(rs),
+                "{:?} {:?}",
+                ls,
+                eza
+            );
+        }
+
+        assert!(LsColors::from_vars(None, Some("reset")).is_some());
+        assert!(LsColors::from_vars(Some(""), None).is_none());
+    }
+}
@@ -1,22 +1,48 @@
-mod dir_util;
-mod history;
-mod icons;
-mod mode;
+mod command;
+mod config;
+mod listing;
+mod multiplexer;
 mod screen;
+#[cfg(test)]
+mod temp_dir;
+mod theme;
 mod visualize;
 
 use clap::Parser;
-use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
+use command::CommandKind;
+use config::Config;
+use crossterm::event::Event;
 use crossterm::terminal::disable_raw_mode;
-use dir_util::{get_dirs_files, starts_with};
-use history::PathHistory;
+use listing::Format;
+use multiplexer::Multiplexer;
 use screen::Screen;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 use std::{env, io};
+use theme::{ColorDepth, Theme};
+use twiggle::dir_util::{
+    extract, filter_hidden, is_browsable, list_dir, nearest_existing_ancestor, physical_path,
+    shell_dir,
+};
+use twiggle::event_source::{EventSource, ScriptedEvents, TerminalEvents};
+use twiggle::filter::FilterKind;
+use twiggle::hints::{DEFAULT_ALPHABET, parse_alphabet};
+use twiggle::icons::{IconOverrides, IconProvider, IconSet};
+use twiggle::ls_colors::LsColors;
+use twiggle::mode::Mode;
+use twiggle::navigator::{Action, Effect, Navigator};
+use twiggle::paging::Paging;
+use twiggle::project::{
+    DEFAULT_PROJECTS_DEPTH, DEFAULT_ROOT_MARKERS, find_repositories, find_root,
+};
+use twiggle::prompt::{Env, complete, expand, load_history, save_history};
+use twiggle::tree::Tree;
+use twiggle::usage::UsageScan;
 use visualize::View;
 
-use crate::dir_util::{build_char_map, filter_hidden};
-use crate::mode::Mode;
+// How often Usage mode redraws while sizes are computed.
+const USAGE_REFRESH: Duration = Duration::from_millis(100);
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
@@ -24,8 +50,21 @@ struct Args {
     #[arg(short, long, default_value_t = false)]
     no_colors: bool,
 
-    #[arg(short, long, default_value_t = false)]
-    icons: bool,
+    /// Enables icons, the set is nerd (default), unicode or ascii
+    #[arg(
+        short,
+        long,
+        value_name = "SET",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "",
+        value_parser = ["", "nerd", "unicode", "ascii"]
+    )]
+    icons: Option<String>,
+
+    /// default, light, high-contrast, solarized, catppuccin or a theme from the config
+    #[arg(long, value_name = "NAME")]
+    theme: Option<String>,
 
     #[arg(long, default_value_t = false)]
     debug: bool,
@@ -35,23 +74,183 @@ struct Args {
 
     #[arg(long, default_value_t = false)]
     clear: bool,
+
+    /// Prints the path with symlinks resolved, like `cd -P`
+    #[arg(short = 'P', long, default_value_t = false)]
+    physical: bool,
+
+    /// Labels every directory with one or two keys instead of grouping them by letter
+    #[arg(long, default_value_t = false)]
+    hints: bool,
+
+    /// Levels shown in tree mode (<C-r>)
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u16).range(1..))]
+    tree_depth: Option<u16>,
+
+    /// Overrides `commands.exec` from the config, the command run with <C-x>. `{}` is replaced by the directory, which is also passed as `$1`
+    #[arg(long, value_name = "CMD")]
+    exec: Option<String>,
+
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+
+    /// Prints the listing as JSON and exits
+    #[arg(long, default_value_t = false, conflicts_with = "list")]
+    json: bool,
+
+    /// Prints the listing as tab-separated text and exits
+    #[arg(long, default_value_t = false)]
+    list: bool,
+
+    /// Replays keys without a terminal and prints the resulting directory, e.g. 'sr<BS>~'
+    #[arg(long, value_name = "KEYS")]
+    keys: Option<String>,
 }
 
 fn main() -> io::Result<()> {
     let args = Args::parse();
+    let mut config = Config::load(args.config.as_deref())?;
+
+    if args.exec.is_some() {
+        config.commands.exec = args.exec;
+    }
+
+    let keybinds = match &config.select_keys {
+        Some(keys) => parse_alphabet(keys)
+            .map_err(|message| io::Error::new(io::ErrorKind::InvalidInput, message))?
+            .into_iter()
+            .collect(),
+        None => String::from("1234567890"),
+    };
+    let hints = args.hints || config.hints == Some(true);
+    let hint_alphabet = match &config.hint_alphabet {
+        Some(alphabet) => parse_alphabet(alphabet)
+            .map_err(|message| io::Error::new(io::ErrorKind::InvalidInput, message))?,
+        None => DEFAULT_ALPHABET.chars().collect(),
+    };
+
+    // Listings always contain icons.
+    let listing = args.json || args.list;
+    let icon_flag = if listing {
+        Some(args.icons.as_deref().unwrap_or(""))
+    } else {
+        args.icons.as_deref()
+    };
+    let icons = icon_provider(icon_flag, &config)?;
+
+    if listing {
+        let format = if args.json { Format::Json } else { Format::Tsv };
+        let current_dir = env::current_dir()?;
+        let Entries {
+            mut dirs,
+            mut files,
+            ..
+        } = read_entries(&current_dir, config.browse_archives != Some(false))?;
+
+        if args.hide {
+            dirs = filter_hidden(&dirs);
+            files = filter_hidden(&files);
+        }
 
-    let keybinds = "1234567890";
+        let icons = icons.expect("icons are enabled");
+        let hint_alphabet = hints.then_some(hint_alphabet.as_slice());
+        return listing::print(
+            format,
+            &current_dir,
+            &dirs,
+            &files,
+            &keybinds,
+            hint_alphabet,
+            &icons,
+        );
+    }
+
+    let mut events: Box<dyn EventSource> = match &args.keys {
+        Some(keys) => Box::new(ScriptedEvents::parse(keys)?),
+        None => Box::new(TerminalEvents),
+    };
+
+    // https://no-color.org
+    let no_colors = args.no_colors || env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+
+    let theme = if no_colors {
+        Theme::monochrome()
+    } else {
+        let name = args.theme.or(config.theme.clone());
+        let name = name.as_deref().unwrap_or("default");
+        Theme::load(name, &config.themes)?.downgrade(ColorDepth::detect())
+    };
+
+    let ls_colors = if no_colors || config.ls_colors == Some(false) {
+        None
+    } else {
+        LsColors::from_env()
+    };
 
     let screen = Screen::new();
     let mut view: View = View::new(
         screen,
-        keybinds.to_string(),
-        !args.no_colors,
-        args.icons,
+        theme,
+        ls_colors,
+        icons,
         args.debug,
+        args.keys.is_some(),
+        args.physical,
     );
+    if config.project_badges == Some(false) {
+        view.hide_project_badges();
+    }
+    if config.browse_archives == Some(false) {
+        view.hide_archives();
+    }
+
+    let tree_depth = args
+        .tree_depth
+        .map(usize::from)
+        .or(config.tree_depth)
+        .unwrap_or(2);
+
+    let mut navigator = Navigator::new(
+        env::current_dir()?,
+        Env::from_process(),
+        keybinds.clone(),
+        !args.hide,
+    );
+    navigator.tree = Tree::new(tree_depth);
+    navigator.hints = hints;
+    navigator.hint_alphabet = hint_alphabet;
+
+    if let Some(name) = &config.filter {
+        navigator.filter_kind = FilterKind::from_name(name).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Unknown filter {}", name),
+            )
+        })?;
+    }
+    navigator.sticky_filter = config.sticky_filter == Some(true);
 
-    input_loop(&mut view, keybinds, !args.hide)?;
+    // Replayed keys neither see nor change the prompt history.
+    let keep_history = args.keys.is_none();
+    if keep_history {
+        navigator.prompt_history = load_history();
+    }
+    let loaded_history = navigator.prompt_history.clone();
+
+    input_loop(
+        &mut view,
+        events.as_mut(),
+        &config,
+        &mut navigator,
+        args.physical,
+    )?;
+
+    if keep_history
+        && navigator.prompt_history != loaded_history
+        && let Err(err) = save_history(&navigator.prompt_history)
+    {
+        eprintln!("Could not save the prompt history: {}", err);
+    }
 
     if args.clear {
         view.clear_screen()?;
@@ -60,79 +259,167 @@ fn main() -> io::Result<()> {
     Ok(())
 }
 
-fn input_loop(view: &mut View, keybinds: &str, show_hidden_default: bool) -> io::Result<()> {
-    let mut prefix = String::from("");
-    let mut current_page: Option<usize> = None;
-    let mut show_hidden = show_hidden_default;
-    let mut mode = Mode::Normal;
-    let mut is_dirty;
-    let mut has_terminated;
-    let mut cursor_index: Option<usize> = None;
-    let mut history = PathHistory::new();
-    history.push(env::current_dir()?);
+// None if icons are disabled. `--icons` without a set falls back to the config.
+fn icon_provider(flag: Option<&str>, config: &Config) -> io::Result<Option<IconProvider>> {
+    let name = match flag {
+        None => return Ok(None),
+        Some("") => config.icons.set.as_deref().unwrap_or("nerd"),
+        Some(name) => name,
+    };
+
+    let set = IconSet::from_name(name).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Unknown icon set {}", name),
+        )
+    })?;
+
+    let overrides = IconOverrides {
+        directories: config.icons.directories.clone(),
+        files: config.icons.files.clone(),
+        extensions: config
+            .icons
+            .extensions
+            .iter()
+            .map(|(ext, icon)| (ext.to_lowercase(), icon.clone()))
+            .collect(),
+        globs: config
+            .icons
+            .globs
+            .iter()
+            .map(|(glob, icon)| (glob.clone(), icon.clone()))
+            .collect(),
+    };
+
+    Ok(Some(IconProvider::new(set, overrides)))
+}
+
+fn input_loop(
+    view: &mut View,
+    events: &mut dyn EventSource,
+    config: &Config,
+    navigator: &mut Navigator,
+    physical: bool,
+) -> io::Result<()> {
+    let mut usage_scan: Option<UsageScan> = None;
+    let mut entered: Option<Entries> = None;
+    let browse_archives = config.browse_archives != Some(false);
 
     loop {
-        let current_dir = std::env::current_dir()?;
-        let (mut dirs, mut files) = get_dirs_files()?;
+        if !is_browsable(&navigator.current_dir) {
+            entered = recover_vanished_dir(view, navigator, browse_archives);
+        }
+        load_entries(view, navigator, entered.take(), browse_archives);
+        load_tree(navigator, browse_archives);
+        if sync_usage_scan(&mut usage_scan, navigator) {
+            view.dirty();
+        }
+        navigator.set_paging(Paging::new(
+            navigator.keybinds().chars().count(),
+            view.select_rows(),
+        ));
+
+        view.debug_message(format!("Show hidden files: {}", navigator.show_hidden));
+        view.debug_message(format!("History index: {}", navigator.history.index));
+        view.debug_message(format!(
+            "History length: {}",
+            navigator.history.buffer.len()
+        ));
+        view.debug_message(format!("Cursor index: {:?}", navigator.cursor_index));
+
+        draw(view, navigator)?;
+
+        // Only the sizes change while nothing is typed, the entries are not read again.
+        while usage_scan.is_some() && !events.poll(USAGE_REFRESH)? {
+            if sync_usage_scan(&mut usage_scan, navigator) {
+                view.dirty();
+                draw(view, navigator)?;
+            }
+        }
 
-        if !show_hidden {
-            dirs = filter_hidden(&dirs);
-            files = filter_hidden(&files);
+        let event = match events.next_event()? {
+            Some(event) => event,
+            None => {
+                print_path(&shell_dir(&navigator.current_dir), physical)?;
+                break;
+            }
+        };
+
+        let e = match event {
+            Event::Key(e) => e,
+            _ => continue,
+        };
+
+        // Typing modes keep control keys like <C-w> for themselves.
+        if let Some(kind) = CommandKind::from_key(e)
+            && matches!(navigator.mode, Mode::Normal | Mode::Select | Mode::Tree)
+        {
+            let highlighted = if kind.follows_cursor() {
+                navigator.highlighted_dir()
+            } else {
+                None
+            };
+            let target = highlighted.unwrap_or(navigator.current_dir.clone());
+            run_command(view, config, kind, &target)?;
+            continue;
         }
 
-        view.debug_message(format!("Show hidden files: {}", show_hidden));
-        view.debug_message(format!("History index: {}", history.index));
-        view.debug_message(format!("History length: {}", history.buffer.len()));
-        view.debug_message(format!("Cursor index: {:?}", cursor_index));
+        let action = match Action::from_key(e) {
+            Some(a) => a,
+            None => continue,
+        };
 
-        view.change_mode(mode);
-        view.prepare_screen()?;
-        view.display(
-            &current_dir,
-            &dirs,
-            &files,
-            &prefix,
-            current_page.unwrap_or_default(),
-            &history,
-            &cursor_index,
-        )?;
-        view.clear_rest()?;
-
-        if let Event::Key(e) = event::read()? {
-            // view.debug_message(format!("Current char: {} {}", e.code, e.modifiers));
-            match mode {
-                Mode::Normal => {
-                    (is_dirty, has_terminated) = handle_normal_mode(
-                        e,
-                        &mut prefix,
-                        &mut show_hidden,
-                        &mut mode,
-                        &mut current_page,
-                        &dirs,
-                        &mut history,
-                        &mut cursor_index,
-                    )?;
+        match navigator.handle(action) {
+            Effect::None => {}
+            Effect::Redraw => view.dirty(),
+            Effect::Complete(text) => {
+                navigator.set_completion(complete(&text, &navigator.current_dir, &navigator.env));
+                view.dirty();
+            }
+            Effect::FindRoot => {
+                let root_markers = match &config.root_markers {
+                    Some(markers) => markers.clone(),
+                    None => DEFAULT_ROOT_MARKERS.map(String::from).to_vec(),
+                };
+
+                match find_root(&navigator.current_dir, &root_markers) {
+                    Some(root) => {
+                        let effect = navigator.go_to(root);
+                        entered = enter_dir(view, navigator, effect, browse_archives);
+                    }
+                    None => view.error_message(format!(
+                        "No project root above {}",
+                        navigator.current_dir.display()
+                    )),
                 }
-                Mode::Select => {
-                    (is_dirty, has_terminated) = handle_select_mode(
-                        e,
-                        &mut prefix,
-                        &mut show_hidden,
-                        &mut mode,
-                        &mut current_page,
-                        keybinds,
-                        &dirs,
-                        &mut history,
-                        &mut cursor_index,
-                    )?;
+                view.dirty();
+            }
+            Effect::Extract(path) => {
+                match extract(&path) {
+                    Ok(dest) => {
+                        let effect = navigator.go_to(dest);
+                        entered = enter_dir(view, navigator, effect, browse_archives);
+                    }
+                    Err(err) => {
+                        view.error_message(format!("Could not extract {}: {}", path.display(), err))
+                    }
                 }
+                view.dirty();
             }
-
-            if is_dirty {
+            Effect::FindProjects => {
+                show_projects(view, config, navigator);
                 view.dirty();
             }
-
-            if has_terminated {
+            effect @ Effect::ChangeDir(_) => {
+                entered = enter_dir(view, navigator, effect, browse_archives);
+                view.dirty();
+            }
+            Effect::Exit(path) => {
+                disable_raw_mode()?;
+                match path {
+                    Some(p) => print_path(&shell_dir(&p), physical)?,
+                    None => println!("."),
+                }
                 break;
             }
         }
@@ -140,286 +427,219 @@ fn input_loop(view: &mut View, keybinds: &str, show_hidden_default: bool) -> io:
     Ok(())
 }
 
-#[allow(clippy::too_many_arguments)] // I know it's bad
-fn handle_normal_mode(
-    e: KeyEvent,
-    prefix: &mut String,
-    show_hidden: &mut bool,
-    mode: &mut Mode,
-    current_page: &mut Option<usize>,
-    dirs: &[PathBuf],
-    history: &mut PathHistory,
-    cursor_index: &mut Option<usize>,
-) -> io::Result<(bool, bool)> {
-    match e.code {
-        KeyCode::Char(c) => {
-            if check_hidden_key(e, c, show_hidden, cursor_index)
-                || check_home_key(c, mode, history, cursor_index)?
-            {
-                return Ok((true, false));
-            };
+fn draw(view: &mut View, navigator: &Navigator) -> io::Result<()> {
+    view.prepare_screen()?;
+    view.display(navigator)?;
+    view.clear_rest()
+}
 
-            *prefix = String::from("");
-            let filtered_dirs = starts_with(dirs, &c.to_string());
+// Writes the exact bytes of the path, so `cd` also works for names that are not UTF-8.
+fn print_path(path: &Path, physical: bool) -> io::Result<()> {
+    let path = if physical {
+        physical_path(path)
+    } else {
+        path.to_path_buf()
+    };
+
+    let mut stdout = io::stdout().lock();
+    stdout.write_all(path.as_os_str().as_encoded_bytes())?;
+    writeln!(stdout)
+}
 
-            if filtered_dirs.is_empty() {
-                return Ok((false, false));
-            }
+// The directories and files of `dir`, read when the navigator entered it.
+struct Entries {
+    dir: PathBuf,
+    dirs: Vec<PathBuf>,
+    files: Vec<PathBuf>,
+}
 
-            if filtered_dirs.len() > 1 {
-                *mode = Mode::Select;
-                *current_page = Some(0);
-                *cursor_index = Some(0);
-                *prefix = c.to_string();
-                return Ok((true, false));
-            }
+// Archives on disk are entered like directories.
+fn read_entries(dir: &Path, browse_archives: bool) -> io::Result<Entries> {
+    let (dirs, files) = list_dir(dir, browse_archives)?;
 
-            *cursor_index = None;
-            env::set_current_dir(filtered_dirs[0].file_name().unwrap())?;
-            history.push(env::current_dir()?);
+    Ok(Entries {
+        dir: dir.to_path_buf(),
+        dirs,
+        files,
+    })
+}
 
-            Ok((true, false))
+// Reads the directory an `Effect::ChangeDir` moved to, directories that cannot be
+// read are reverted. The entries are passed on to the next `load_entries`.
+fn enter_dir(
+    view: &mut View,
+    navigator: &mut Navigator,
+    effect: Effect,
+    browse_archives: bool,
+) -> Option<Entries> {
+    let path = match effect {
+        Effect::ChangeDir(path) => path,
+        _ => return None,
+    };
+
+    match read_entries(&path, browse_archives) {
+        Ok(entries) => Some(entries),
+        Err(err) => {
+            navigator.revert();
+            view.error_message(format!("Cannot open {}: {}", path.display(), err));
+            None
         }
-        KeyCode::Backspace => {
-            *cursor_index = None;
-            env::set_current_dir("..")?;
-            history.push(env::current_dir()?);
+    }
+}
 
-            Ok((true, false))
+// The process never changes its own working directory, the navigator's path is the only state.
+// Entries read by `enter_dir` are used as they are, otherwise the directory is read again.
+fn load_entries(
+    view: &mut View,
+    navigator: &mut Navigator,
+    entered: Option<Entries>,
+    browse_archives: bool,
+) {
+    let entries = match entered.filter(|entries| entries.dir == navigator.current_dir) {
+        Some(entries) => Ok(entries),
+        None => read_entries(&navigator.current_dir, browse_archives),
+    };
+
+    match entries {
+        Ok(entries) => navigator.set_entries(entries.dirs, entries.files),
+        Err(err) => {
+            navigator.set_entries(Vec::new(), Vec::new());
+            view.error_message(format!(
+                "Could not read {}: {}",
+                navigator.current_dir.display(),
+                err
+            ));
         }
-        KeyCode::Esc => {
-            println!(".");
-            Ok((false, true))
-        }
-        KeyCode::Enter => {
-            if cursor_index.is_some() {
-                let index = cursor_index.unwrap_or(0);
-                let char_map = build_char_map(dirs);
+    }
+}
 
-                let c = char_map.iter().nth(index).unwrap().0;
+// Reads the subdirectories of every expanded tree node, unreadable ones stay empty.
+fn load_tree(navigator: &mut Navigator, browse_archives: bool) {
+    loop {
+        let unloaded = navigator.unloaded_tree_dirs();
+        if unloaded.is_empty() {
+            break;
+        }
 
-                let filtered_dirs = starts_with(dirs, &c.to_string());
+        for dir in unloaded {
+            let dirs = list_dir(&dir, browse_archives)
+                .map(|(dirs, _)| dirs)
+                .unwrap_or_default();
+            navigator.set_tree_children(dir, dirs);
+        }
+    }
+}
 
-                if filtered_dirs.is_empty() {
-                    return Ok((false, false));
-                }
+fn show_projects(view: &mut View, config: &Config, navigator: &mut Navigator) {
+    let dir = match &config.projects_dir {
+        Some(dir) => expand(dir, &navigator.current_dir, &navigator.env),
+        None => {
+            view.error_message(String::from("No projects_dir configured"));
+            return;
+        }
+    };
 
-                if filtered_dirs.len() > 1 {
-                    *mode = Mode::Select;
-                    *current_page = Some(0);
-                    *cursor_index = Some(0);
-                    *prefix = c.to_string();
-                    return Ok((true, false));
-                }
+    let depth = config.projects_depth.unwrap_or(DEFAULT_PROJECTS_DEPTH);
+    let repositories = find_repositories(&dir, depth);
 
-                *cursor_index = None;
-                env::set_current_dir(filtered_dirs[0].file_name().unwrap())?;
-                history.push(env::current_dir()?);
+    if repositories.is_empty() {
+        view.error_message(format!("No repositories in {}", dir.display()));
+    } else {
+        navigator.show_projects(dir, repositories);
+    }
+}
 
-                return Ok((true, false));
-            }
+// Starts the scan Usage mode asks for and stops the ones it no longer needs.
+// Returns true if new sizes arrived.
+fn sync_usage_scan(scan: &mut Option<UsageScan>, navigator: &mut Navigator) -> bool {
+    if let Some(dir) = navigator.unscanned_usage_dir() {
+        navigator.start_usage(dir.clone());
+        *scan = Some(UsageScan::start(dir));
+    }
 
-            disable_raw_mode()?;
-            println!("{}", env::current_dir().unwrap().display());
-            Ok((false, true))
-        }
-        KeyCode::Up => {
-            let path = history.go_up();
-
-            match path {
-                Some(p) => {
-                    *cursor_index = None;
-                    env::set_current_dir(p)?;
-                    Ok((true, false))
-                }
-                None => Ok((false, false)),
-            }
+    let mut changed = false;
+    if let Some(scan) = scan.as_ref() {
+        for update in scan.updates() {
+            navigator.update_usage(&scan.dir, update);
+            changed = true;
         }
-        KeyCode::Down => {
-            let path = history.go_down();
-
-            match path {
-                Some(p) => {
-                    *cursor_index = None;
-                    env::set_current_dir(p)?;
-                    Ok((true, false))
-                }
-                None => Ok((false, false)),
-            }
-        }
-        KeyCode::Tab => {
-            if dirs.is_empty() {
-                return Ok((false, false));
-            }
-
-            if cursor_index.is_none() {
-                *cursor_index = Some(0);
-                return Ok((true, false));
-            }
+    }
 
-            let char_map = build_char_map(dirs);
+    if scan.as_ref().map(|scan| scan.dir.as_path()) != navigator.scanning_usage_dir() {
+        *scan = None;
+    }
 
-            *cursor_index = Some((cursor_index.unwrap_or(0) + 1) % char_map.len());
+    changed
+}
 
-            Ok((true, false))
+// Moves to the nearest ancestor that still exists. There is nothing to revert to, so an
+// ancestor that cannot be read is shown empty with the reason.
+fn recover_vanished_dir(
+    view: &mut View,
+    navigator: &mut Navigator,
+    browse_archives: bool,
+) -> Option<Entries> {
+    let vanished = navigator.current_dir.clone();
+    let ancestor = nearest_existing_ancestor(&vanished)?;
+    navigator.go_to(ancestor.clone());
+
+    match read_entries(&ancestor, browse_archives) {
+        Ok(entries) => {
+            view.error_message(format!("{} no longer exists", vanished.display()));
+            Some(entries)
+        }
+        Err(err) => {
+            view.error_message(format!(
+                "{} no longer exists and {} cannot be read: {}",
+                vanished.display(),
+                ancestor.display(),
+                err
+            ));
+            Some(Entries {
+                dir: ancestor,
+                dirs: Vec::new(),
+                files: Vec::new(),
+            })
         }
-        _ => Ok((false, false)),
     }
 }
 
-#[allow(clippy::too_many_arguments)] // I know it's bad
-fn handle_select_mode(
-    e: KeyEvent,
-    prefix: &mut String,
-    show_hidden: &mut bool,
-    mode: &mut Mode,
-    current_page: &mut Option<usize>,
-    keybinds: &str,
-    dirs: &[PathBuf],
-    history: &mut PathHistory,
-    cursor_index: &mut Option<usize>,
-) -> io::Result<(bool, bool)> {
-    match e.code {
-        KeyCode::Char(c) => {
-            if check_hidden_key(e, c, show_hidden, cursor_index)
-                || check_home_key(c, mode, history, cursor_index)?
-            {
-                return Ok((true, false));
-            };
-
-            let filtered_dirs = starts_with(dirs, prefix);
-
-            let n = filtered_dirs.len();
-            let max_page = if n == 0 { 0 } else { (n - 1) / 10 };
-
-            *cursor_index = Some(0);
+fn run_command(view: &mut View, config: &Config, kind: CommandKind, dir: &Path) -> io::Result<()> {
+    // Replayed keys must not start programs, they could wait for a terminal forever.
+    if view.is_headless() {
+        view.error_message(format!("{:?} cannot run while replaying keys", kind));
+        return Ok(());
+    }
 
-            if e.modifiers.contains(KeyModifiers::CONTROL) {
-                match c {
-                    'f' => {
-                        *current_page = current_page.map(|p| (p + 1).min(max_page));
-                    }
-                    'b' => {
-                        *current_page = current_page.map(|p| p.saturating_sub(1));
-                    }
-                    'n' => {
-                        *current_page = current_page.map(|p| p + 1);
-                        if current_page.unwrap_or(0) > max_page {
-                            *current_page = Some(0);
-                        }
-                    }
-                    _ => return Ok((false, false)),
+    if let CommandKind::Pane = kind {
+        match Multiplexer::detect() {
+            Some(m) => {
+                if let Err(err) = m.open(&config.multiplexer, dir) {
+                    view.error_message(format!("Could not open {:?} pane: {}", m, err));
                 }
-                return Ok((true, false));
             }
-
-            let index = match keybinds.find(c) {
-                Some(i) => i + current_page.unwrap_or_default() * 10,
-                None => {
-                    prefix.push(c);
-                    *current_page = Some(0);
-                    return Ok((true, false));
-                }
-            };
-
-            let dir = match filtered_dirs.get(index) {
-                Some(d) => d,
-                None => return Ok((false, false)),
-            };
-
-            env::set_current_dir(dir.file_name().unwrap())?;
-            history.push(env::current_dir()?);
-            *cursor_index = None;
-            *mode = Mode::Normal;
-            Ok((true, false))
-        }
-        KeyCode::Backspace => {
-            if prefix.len() > 1 {
-                prefix.pop();
-                *current_page = Some(0);
-                *cursor_index = Some(0);
-            } else {
-                *mode = Mode::Normal;
-                *current_page = None;
-                *cursor_index = None;
-            }
-            Ok((true, false))
+            None => view.error_message(String::from("Not running inside tmux or zellij")),
         }
-        KeyCode::Esc => {
-            prefix.clear();
-            *current_page = Some(0);
-            *mode = Mode::Normal;
-            Ok((true, false))
-        }
-        KeyCode::Enter => {
-            let index = cursor_index.unwrap_or(0) + current_page.unwrap_or(0) % 10 * 10;
-
-            let filtered_dirs = starts_with(dirs, prefix);
-            let dir = match filtered_dirs.get(index) {
-                Some(d) => d,
-                None => return Ok((false, false)),
-            };
+        return Ok(());
+    }
 
-            env::set_current_dir(dir.file_name().unwrap())?;
-            history.push(env::current_dir()?);
-            *mode = Mode::Normal;
-            *cursor_index = None;
-            Ok((true, false))
+    let template = match kind.template(&config.commands) {
+        Some(t) => t,
+        None => {
+            view.error_message(format!("No command configured for {:?}", kind));
+            return Ok(());
         }
-        KeyCode::Tab => {
-            if dirs.is_empty() {
-                return Ok((false, false));
-            }
-
-            let filtered_dirs = starts_with(dirs, prefix);
-            let n = filtered_dirs.len().min(10);
-            let max_page = if n == 0 { 0 } else { (n - 1) / 10 };
-
-            let cursor_len = if max_page == current_page.unwrap_or(0) {
-                n
-            } else {
-                filtered_dirs.len() % 10
-            };
-
-            if cursor_len == 0 {
-                return Ok((false, false));
-            }
-
-            *cursor_index = Some((cursor_index.unwrap_or(0) + 1) % cursor_len);
+    };
 
-            Ok((true, false))
-        }
-        _ => Ok((false, false)),
-    }
-}
+    view.suspend()?;
+    let status = command::spawn(&template, dir);
+    view.resume();
 
-fn check_hidden_key(
-    e: KeyEvent,
-    c: char,
-    show_hidden: &mut bool,
-    cursor_index: &mut Option<usize>,
-) -> bool {
-    if e.modifiers.contains(KeyModifiers::CONTROL) && c == 's' {
-        *show_hidden = !*show_hidden;
-        *cursor_index = None;
-        return true;
+    match status {
+        Ok(s) if !s.success() => view.error_message(format!("{} exited with {}", template, s)),
+        Err(err) => view.error_message(format!("Could not run {}: {}", template, err)),
+        _ => {}
     }
-    false
-}
 
-fn check_home_key(
-    c: char,
-    mode: &mut Mode,
-    history: &mut PathHistory,
-    cursor_index: &mut Option<usize>,
-) -> io::Result<bool> {
-    if c == '~' {
-        let home_dir = dirs::home_dir().expect("Could not find home directory.");
-        env::set_current_dir(&home_dir)?;
-        history.push(env::current_dir()?);
-        *mode = Mode::Normal;
-        *cursor_index = None;
-        return Ok(true);
-    }
-    Ok(false)
+    Ok(())
 }
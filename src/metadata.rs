@@ -0,0 +1,321 @@
+use std::{
+    fs::{self, Metadata},
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+use crate::dir_util::is_directory;
+
+// Number of columns in the long view.
+pub const COLUMNS: usize = 5;
+
+// Columns are dropped in this order when the terminal is too narrow:
+// owner, permissions, children, modified and size last.
+const DROP_ORDER: [usize; COLUMNS] = [1, 0, 4, 2, 3];
+
+// The columns of the long view, already formatted.
+#[derive(Debug, Clone, Default)]
+pub struct EntryInfo {
+    pub permissions: String,
+    pub owner: String,
+    pub modified: String,
+    pub size: String,
+    // Only filled for directories.
+    pub children: String,
+}
+
+impl EntryInfo {
+    // Entries that vanished or cannot be read show `-` in every column.
+    pub fn read(path: &Path, now: SystemTime) -> Self {
+        let meta = match fs::symlink_metadata(path) {
+            Ok(m) => m,
+            Err(_) => {
+                return EntryInfo {
+                    permissions: String::from("-"),
+                    owner: String::from("-"),
+                    modified: String::from("-"),
+                    size: String::from("-"),
+                    children: String::new(),
+                };
+            }
+        };
+
+        let is_dir = is_directory(path);
+
+        EntryInfo {
+            permissions: permissions(&meta),
+            owner: owner(&meta),
+            modified: meta
+                .modified()
+                .map(|time| relative_time(time, now))
+                .unwrap_or_else(|_| String::from("-")),
+            size: if is_dir {
+                String::from("-")
+            } else {
+                human_size(meta.len())
+            },
+            children: if is_dir {
+                fs::read_dir(path)
+                    .map(|entries| entries.count().to_string())
+                    .unwrap_or_else(|_| String::from("-"))
+            } else {
+                String::new()
+            },
+        }
+    }
+
+    pub fn fields(&self) -> [&str; COLUMNS] {
+        [
+            &self.permissions,
+            &self.owner,
+            &self.modified,
+            &self.size,
+            &self.children,
+        ]
+    }
+}
+
+// Width of every column over all rows, None for columns that do not fit next to
+// `reserved` characters within `width`.
+pub fn fit_columns(rows: &[EntryInfo], reserved: usize, width: usize) -> [Option<usize>; COLUMNS] {
+    let mut widths = [None; COLUMNS];
+
+    for row in rows {
+        for (i, field) in row.fields().iter().enumerate() {
+            let len = field.chars().count();
+            widths[i] = Some(widths[i].unwrap_or(0).max(len));
+        }
+    }
+
+    // Every column is followed by two spaces.
+    let total = |widths: &[Option<usize>; COLUMNS]| {
+        reserved + widths.iter().flatten().map(|w| w + 2).sum::<usize>()
+    };
+
+    for i in DROP_ORDER {
+        if total(&widths) <= width {
+            break;
+        }
+        widths[i] = None;
+    }
+
+    widths
+}
+
+// Pads the visible columns, numbers are aligned to the right.
+pub fn format_columns(info: &EntryInfo, widths: &[Option<usize>; COLUMNS]) -> String {
+    info.fields()
+        .iter()
+        .zip(widths)
+        .enumerate()
+        .filter_map(|(i, (field, width))| {
+            let width = (*width)?;
+            Some(if i >= 2 {
+                format!("{:>width$}  ", field)
+            } else {
+                format!("{:<width$}  ", field)
+            })
+        })
+        .collect()
+}
+
+// Like `ls -l`, e.g. `drwxr-xr-x`.
+#[cfg(unix)]
+fn permissions(meta: &Metadata) -> String {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mode = meta.permissions().mode();
+    let file_type = if meta.is_dir() {
+        'd'
+    } else if meta.file_type().is_symlink() {
+        'l'
+    } else {
+        '-'
+    };
+
+    let mut s = String::from(file_type);
+    for shift in [6, 3, 0] {
+        let bits = (mode >> shift) & 0o7;
+        s.push(if bits & 0o4 != 0 { 'r' } else { '-' });
+        s.push(if bits & 0o2 != 0 { 'w' } else { '-' });
+        s.push(if bits & 0o1 != 0 { 'x' } else { '-' });
+    }
+    s
+}
+
+#[cfg(not(unix))]
+fn permissions(meta: &Metadata) -> String {
+    if meta.permissions().readonly() {
+        String::from("r-")
+    } else {
+        String::from("rw")
+    }
+}
+
+// Users are looked up in /etc/passwd, unknown ones are shown by their id.
+#[cfg(unix)]
+fn owner(meta: &Metadata) -> String {
+    use std::{collections::HashMap, os::unix::fs::MetadataExt, sync::OnceLock};
+
+    static USERS: OnceLock<HashMap<u32, String>> = OnceLock::new();
+
+    let users = USERS.get_or_init(|| {
+        fs::read_to_string("/etc/passwd")
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split(':');
+                let name = fields.next()?;
+                let uid = fields.nth(1)?.parse().ok()?;
+                Some((uid, name.to_string()))
+            })
+            .collect()
+    });
+
+    let uid = meta.uid();
+    users.get(&uid).cloned().unwrap_or_else(|| uid.to_string())
+}
+
+#[cfg(not(unix))]
+fn owner(_meta: &Metadata) -> String {
+    String::from("-")
+}
+
+// E.g. `now`, `5m ago`, `3d ago` or `2y ago`. Times in the future count as now.
+pub fn relative_time(time: SystemTime, now: SystemTime) -> String {
+    let secs = now.duration_since(time).unwrap_or(Duration::ZERO).as_secs();
+
+    let units = [
+        (365 * 24 * 3600, "y"),
+        (30 * 24 * 3600, "mo"),
+        (7 * 24 * 3600, "w"),
+        (24 * 3600, "d"),
+        (3600, "h"),
+        (60, "m"),
+    ];
+
+    units
+        .iter()
+        .find(|(unit, _)| secs >= *unit)
+        .map(|(unit, name)| format!("{}{} ago", secs / unit, name))
+        .unwrap_or_else(|| String::from("now"))
+}
+
+// E.g. `512B`, `1.5K` or `23M`, with one decimal below 10.
+pub fn human_size(bytes: u64) -> String {
+    let units = ["B", "K", "M", "G", "T", "P"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+
+    // Sizes that round to 1024 move up too, so there is no `1024K`.
+    while size.round() >= 1024.0 && unit < units.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{}B", bytes)
+    } else if (size * 10.0).round() < 100.0 {
+        format!("{:.1}{}", size, units[unit])
+    } else {
+        format!("{:.0}{}", size, units[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn human_sizes() {
+        let cases = [
+            (0, "0B"),
+            (1023, "1023B"),
+            (1024, "1.0K"),
+            (1536, "1.5K"),
+            (10 * 1024 - 1, "10K"),
+            (10 * 1024, "10K"),
+            (1024 * 1024 - 1, "1.0M"),
+            (1024 * 1024, "1.0M"),
+            (5 * 1024 * 1024 + 1024 * 1024 / 2, "5.5M"),
+            (1024 * 1024 * 1024, "1.0G"),
+            (u64::MAX, "16384P"),
+        ];
+
+        for (bytes, expected) in cases {
+            assert_eq!(human_size(bytes), expected, "{}", bytes);
+        }
+    }
+
+    #[test]
+    fn relative_times() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(100 * 365 * 24 * 3600);
+        let ago = |secs| now - Duration::from_secs(secs);
+
+        let cases = [
+            (now, "now"),
+            (ago(59), "now"),
+            (ago(60), "1m ago"),
+            (ago(3599), "59m ago"),
+            (ago(3600), "1h ago"),
+            (ago(24 * 3600), "1d ago"),
+            (ago(7 * 24 * 3600), "1w ago"),
+            (ago(30 * 24 * 3600), "1mo ago"),
+            (ago(365 * 24 * 3600), "1y ago"),
+            (ago(3 * 365 * 24 * 3600), "3y ago"),
+            // Clocks of other machines can be ahead.
+            (now + Duration::from_secs(3600), "now"),
+        ];
+
+        for (time, expected) in cases {
+            assert_eq!(relative_time(time, now), expected);
+        }
+    }
+
+    fn row(permissions: &str, owner: &str, modified: &str, size: &str) -> EntryInfo {
+        EntryInfo {
+            permissions: permissions.to_string(),
+            owner: owner.to_string(),
+            modified: modified.to_string(),
+            size: size.to_string(),
+            children: String::new(),
+        }
+    }
+
+    #[test]
+    fn columns_are_dropped_when_narrow() {
+        let rows = [
+            row("drwxr-xr-x", "me", "5m ago", "-"),
+            row("-rw-r--r--", "somebody", "now", "1.5K"),
+        ];
+        // 10 + 8 + 6 + 4 + 0, two spaces after each.
+        let all = 10 + 8 + 6 + 4 + 5 * 2;
+
+        let cases = [
+            (all + 20, [Some(10), Some(8), Some(6), Some(4), Some(0)]),
+            (all + 10, [Some(10), Some(8), Some(6), Some(4), Some(0)]),
+            (all + 9, [Some(10), None, Some(6), Some(4), Some(0)]),
+            (10 + 20, [None, None, Some(6), Some(4), Some(0)]),
+            (10 + 15, [None, None, Some(6), Some(4), None]),
+            (10 + 6, [None, None, None, Some(4), None]),
+            (10 + 5, [None; COLUMNS]),
+            (0, [None; COLUMNS]),
+        ];
+
+        for (width, expected) in cases {
+            assert_eq!(fit_columns(&rows, 10, width), expected, "{}", width);
+        }
+    }
+
+    #[test]
+    fn numbers_are_aligned_right() {
+        let widths = fit_columns(&[row("-rw-r--r--", "me", "5m ago", "1.5K")], 0, 80);
+        let info = row("-rw-------", "me", "now", "12B");
+
+        assert_eq!(
+            format_columns(&info, &widths),
+            "-rw-------  me     now   12B    "
+        );
+        assert_eq!(format_columns(&info, &[None; COLUMNS]), "");
+    }
+}
@@ -2,4 +2,14 @@
 pub enum Mode {
     Normal,
     Select,
+    // Several levels of directories at once, see `tree.rs`.
+    Tree,
+    // Typing into the `/` filter, the directories are shown like in Normal mode.
+    Filter,
+    // Typing a path to jump to, see `prompt.rs`.
+    Prompt,
+    // Repositories below the projects directory, see `project.rs`.
+    Projects,
+    // Recursive sizes of the children of the current directory, see `usage.rs`.
+    Usage,
 }
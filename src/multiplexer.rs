@@ -0,0 +1,155 @@
+use std::{
+    env,
+    ffi::OsString,
+    io,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use crate::config::{MultiplexerCommand, Multiplexers};
+
+#[derive(Debug, Clone, Copy)]
+pub enum Multiplexer {
+    Tmux,
+    Zellij,
+}
+
+impl Multiplexer {
+    pub fn detect() -> Option<Self> {
+        if env::var_os("TMUX").is_some() {
+            Some(Multiplexer::Tmux)
+        } else if env::var_os("ZELLIJ").is_some() {
+            Some(Multiplexer::Zellij)
+        } else {
+            None
+        }
+    }
+
+    fn command<'a>(&self, multiplexers: &'a Multiplexers) -> &'a MultiplexerCommand {
+        match self {
+            Multiplexer::Tmux => &multiplexers.tmux,
+            Multiplexer::Zellij => &multiplexers.zellij,
+        }
+    }
+
+    fn default_bin(&self) -> &'static str {
+        match self {
+            Multiplexer::Tmux => "tmux",
+            Multiplexer::Zellij => "zellij",
+        }
+    }
+
+    fn default_args(&self) -> &'static [&'static str] {
+        match self {
+            Multiplexer::Tmux => &["new-window", "-c", "{}"],
+            Multiplexer::Zellij => &["action", "new-pane", "--cwd", "{}"],
+        }
+    }
+
+    // Runs without a shell and without touching the terminal, only the new pane sees the directory.
+    pub fn open(&self, multiplexers: &Multiplexers, dir: &Path) -> io::Result<()> {
+        let command = self.command(multiplexers);
+        let bin = command.bin.as_deref().unwrap_or(self.default_bin());
+
+        let args: Vec<OsString> = match &command.args {
+            Some(args) => args.iter().map(|a| replace_dir(a, dir)).collect(),
+            None => self
+                .default_args()
+                .iter()
+                .map(|a| replace_dir(a, dir))
+                .collect(),
+        };
+
+        let output = Command::new(bin).args(args).stdin(Stdio::null()).output()?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(io::Error::other(format!(
+                "{} exited with {}: {}",
+                bin,
+                output.status,
+                stderr.trim()
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+fn replace_dir(arg: &str, dir: &Path) -> OsString {
+    if arg == "{}" {
+        return dir.as_os_str().to_owned();
+    }
+    OsString::from(arg.replace("{}", &dir.to_string_lossy()))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::temp_dir::TempDir;
+    use std::{fs, os::unix::fs::PermissionsExt, path::PathBuf};
+
+    // A stand-in for tmux and zellij that writes its arguments to `argv`, one per line.
+    fn stub(name: &str) -> TempDir {
+        let dir = TempDir::new(name);
+        fs::create_dir_all(dir.path().join("some dir")).unwrap();
+
+        let bin = dir.path().join("stub");
+        fs::write(
+            &bin,
+            "#!/bin/sh\nprintf '%s\\n' \"$@\" > \"$(dirname \"$0\")/argv\"\n",
+        )
+        .unwrap();
+        fs::set_permissions(&bin, fs::Permissions::from_mode(0o755)).unwrap();
+        dir
+    }
+
+    fn open_with_stub(
+        name: &str,
+        multiplexer: Multiplexer,
+        args: Option<Vec<String>>,
+    ) -> (PathBuf, String) {
+        let dir = stub(name);
+        let command = MultiplexerCommand {
+            bin: Some(dir.path().join("stub").to_string_lossy().to_string()),
+            args,
+        };
+        let multiplexers = match multiplexer {
+            Multiplexer::Tmux => Multiplexers {
+                tmux: command,
+                ..Default::default()
+            },
+            Multiplexer::Zellij => Multiplexers {
+                zellij: command,
+                ..Default::default()
+            },
+        };
+
+        let target = dir.path().join("some dir");
+        multiplexer.open(&multiplexers, &target).unwrap();
+        let argv = fs::read_to_string(dir.path().join("argv")).unwrap();
+        (target, argv)
+    }
+
+    #[test]
+    fn tmux_opens_a_new_window_in_the_directory() {
+        let (target, argv) = open_with_stub("tmux", Multiplexer::Tmux, None);
+        assert_eq!(argv, format!("new-window\n-c\n{}\n", target.display()));
+    }
+
+    #[test]
+    fn zellij_opens_a_new_pane_in_the_directory() {
+        let (target, argv) = open_with_stub("zellij", Multiplexer::Zellij, None);
+        assert_eq!(
+            argv,
+            format!("action\nnew-pane\n--cwd\n{}\n", target.display())
+        );
+    }
+
+    #[test]
+    fn configured_args_replace_the_placeholder_inside_arguments() {
+        let args = vec![String::from("split-window"), String::from("-c={}")];
+        let (target, argv) = open_with_stub("args", Multiplexer::Tmux, Some(args));
+        assert_eq!(argv, format!("split-window\n-c={}\n", target.display()));
+    }
+}
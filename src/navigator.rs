@@ -0,0 +1,1228 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::path::{Path, PathBuf};
+
+use crate::dir_util::{build_char_map, filter_hidden, starts_with};
+use crate::filter::{FilterKind, filter_paths};
+use crate::hints::{DEFAULT_ALPHABET, hint_labels};
+use crate::history::PathHistory;
+use crate::mode::Mode;
+use crate::paging::Paging;
+use crate::prompt::{Completion, Env, expand};
+use crate::tree::{Tree, TreeRow};
+use crate::usage::{DiskUsage, ScanState, UsageUpdate};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Char(char),
+    Control(char),
+    Backspace,
+    Enter,
+    Esc,
+    Up,
+    Down,
+    Tab,
+}
+
+impl Action {
+    pub fn from_key(e: KeyEvent) -> Option<Self> {
+        match e.code {
+            KeyCode::Char(c) if e.modifiers.contains(KeyModifiers::CONTROL) => {
+                Some(Action::Control(c))
+            }
+            KeyCode::Char(c) => Some(Action::Char(c)),
+            KeyCode::Backspace => Some(Action::Backspace),
+            KeyCode::Enter => Some(Action::Enter),
+            KeyCode::Esc => Some(Action::Esc),
+            KeyCode::Up => Some(Action::Up),
+            KeyCode::Down => Some(Action::Down),
+            KeyCode::Tab => Some(Action::Tab),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Effect {
+    // Nothing changed.
+    None,
+    // The state changed and has to be redrawn.
+    Redraw,
+    // The current directory changed, its entries have to be passed to `set_entries`.
+    ChangeDir(PathBuf),
+    // Navigation is over, None means it was cancelled.
+    Exit(Option<PathBuf>),
+    // The path prompt wants its text completed, the result goes to `set_completion`.
+    Complete(String),
+    // The caller looks for the project root of the current directory and passes it to `go_to`.
+    FindRoot,
+    // The caller lists the repositories of the projects directory and passes them to `show_projects`.
+    FindProjects,
+    // The caller extracts the directory inside an archive, see `dir_util::extract`.
+    Extract(PathBuf),
+}
+
+// Where the navigator was, with the state that belongs to that directory.
+struct Location {
+    dir: PathBuf,
+    history: PathHistory,
+    filter: String,
+    tree: Tree,
+    usage: Option<DiskUsage>,
+}
+
+// The letter-jump navigation without any terminal or file system access.
+// The caller reads directories and feeds them in with `set_entries`.
+pub struct Navigator {
+    pub current_dir: PathBuf,
+    // Home directory and variables for `~` and typed paths.
+    pub env: Env,
+    pub prefix: String,
+    pub current_page: Option<usize>,
+    pub mode: Mode,
+    pub cursor_index: Option<usize>,
+    pub show_hidden: bool,
+    // Shows permissions, owner, modification time, size and children next to entries.
+    pub long_view: bool,
+    // Shows the parent, the current and the highlighted directory side by side.
+    pub column_view: bool,
+    pub history: PathHistory,
+    pub tree: Tree,
+    // Normal mode labels every directory instead of grouping them by their first letter.
+    pub hints: bool,
+    pub hint_alphabet: Vec<char>,
+    // Names of directories and files have to match the `/` filter to be shown.
+    pub filter: String,
+    pub filter_kind: FilterKind,
+    // Keeps the filter when the directory changes.
+    pub sticky_filter: bool,
+    pub prompt: String,
+    // Directories the prompt text could be completed to.
+    pub prompt_candidates: Vec<String>,
+    // Typed paths, oldest first, browsed with Up and Down in the prompt.
+    pub prompt_history: Vec<String>,
+    prompt_history_index: Option<usize>,
+    // Repositories listed in Projects mode and the directory they were found in.
+    pub projects: Vec<PathBuf>,
+    pub projects_dir: PathBuf,
+    // Sizes of the children of the current directory in Usage mode.
+    pub usage: Option<DiskUsage>,
+    // State before the last directory change, restored by `revert`.
+    previous: Option<Location>,
+    // Text of the path prompt that led to the last directory change, reopened by `revert`.
+    previous_prompt: Option<String>,
+    keybinds: String,
+    paging: Paging,
+    all_dirs: Vec<PathBuf>,
+    all_files: Vec<PathBuf>,
+    dirs: Vec<PathBuf>,
+    files: Vec<PathBuf>,
+}
+
+impl Navigator {
+    pub fn new(current_dir: PathBuf, env: Env, keybinds: String, show_hidden: bool) -> Self {
+        let mut history = PathHistory::new();
+        history.push(&current_dir);
+
+        Navigator {
+            current_dir,
+            env,
+            prefix: String::new(),
+            current_page: None,
+            mode: Mode::Normal,
+            cursor_index: None,
+            show_hidden,
+            long_view: false,
+            column_view: false,
+            history,
+            tree: Tree::new(2),
+            hints: false,
+            hint_alphabet: DEFAULT_ALPHABET.chars().collect(),
+            filter: String::new(),
+            filter_kind: FilterKind::default(),
+            sticky_filter: false,
+            prompt: String::new(),
+            prompt_candidates: Vec::new(),
+            prompt_history: Vec::new(),
+            prompt_history_index: None,
+            projects: Vec::new(),
+            projects_dir: PathBuf::new(),
+            usage: None,
+            previous: None,
+            previous_prompt: None,
+            paging: Paging::new(keybinds.chars().count(), None),
+            keybinds,
+            all_dirs: Vec::new(),
+            all_files: Vec::new(),
+            dirs: Vec::new(),
+            files: Vec::new(),
+        }
+    }
+
+    pub fn set_entries(&mut self, dirs: Vec<PathBuf>, files: Vec<PathBuf>) {
+        self.all_dirs = dirs;
+        self.all_files = files;
+        self.apply_filters();
+    }
+
+    // Directories and files with the hidden filter applied.
+    pub fn dirs(&self) -> &[PathBuf] {
+        &self.dirs
+    }
+
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    pub fn keybinds(&self) -> &str {
+        &self.keybinds
+    }
+
+    pub fn paging(&self) -> Paging {
+        self.paging
+    }
+
+    // Called whenever the room on screen changes, keeps the current page valid.
+    pub fn set_paging(&mut self, paging: Paging) {
+        self.paging = paging;
+
+        if let Some(page) = self.current_page {
+            let n = starts_with(&self.dirs, &self.prefix).len();
+            self.current_page = Some(page.min(paging.max_page(n)));
+        }
+    }
+
+    // The directory under the cursor, if the cursor points at exactly one.
+    pub fn highlighted_dir(&self) -> Option<PathBuf> {
+        let index = self.cursor_index?;
+
+        match self.mode {
+            Mode::Normal | Mode::Filter | Mode::Prompt if self.hints => {
+                self.dirs.get(index).cloned()
+            }
+            Mode::Normal | Mode::Filter | Mode::Prompt => {
+                let char_map = build_char_map(&self.dirs);
+                let (_, directories) = char_map.iter().nth(index)?;
+                if directories.len() == 1 {
+                    Some(directories[0].clone())
+                } else {
+                    None
+                }
+            }
+            Mode::Select => starts_with(&self.dirs, &self.prefix)
+                .get(self.paging.index(self.page(), index))
+                .cloned(),
+            Mode::Tree => self.tree_rows().get(index).map(|row| row.path.clone()),
+            Mode::Projects => self.projects.get(index).cloned(),
+            Mode::Usage => self.usage_dirs().get(index).cloned(),
+        }
+    }
+
+    // The visible rows of Tree mode and their jump labels.
+    pub fn tree_rows(&self) -> Vec<TreeRow> {
+        self.tree.rows(&self.dirs, self.show_hidden)
+    }
+
+    pub fn tree_labels(&self) -> Vec<String> {
+        hint_labels(self.tree_rows().len(), &self.hint_alphabet)
+    }
+
+    // Labels of Normal mode with hints, one per directory.
+    pub fn hint_labels(&self) -> Vec<String> {
+        hint_labels(self.dirs.len(), &self.hint_alphabet)
+    }
+
+    pub fn project_labels(&self) -> Vec<String> {
+        hint_labels(self.projects.len(), &self.hint_alphabet)
+    }
+
+    // Directories in the order of Usage mode, the largest first.
+    pub fn usage_dirs(&self) -> Vec<PathBuf> {
+        match &self.usage {
+            Some(usage) => usage
+                .sorted(&self.dirs)
+                .into_iter()
+                .map(|(path, _)| path)
+                .collect(),
+            None => self.dirs.clone(),
+        }
+    }
+
+    // Directories the caller has to read and pass to `set_tree_children` before drawing.
+    pub fn unloaded_tree_dirs(&self) -> Vec<PathBuf> {
+        match self.mode {
+            Mode::Tree => self.tree.unloaded(&self.dirs, self.show_hidden),
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn set_tree_children(&mut self, dir: PathBuf, dirs: Vec<PathBuf>) {
+        self.tree.set_children(dir, dirs);
+    }
+
+    // The directory the caller has to start a scan for and pass its updates to `update_usage`.
+    pub fn unscanned_usage_dir(&self) -> Option<PathBuf> {
+        match (self.mode, &self.usage) {
+            (Mode::Usage, None) => Some(self.current_dir.clone()),
+            _ => None,
+        }
+    }
+
+    // The directory whose scan is still wanted, other scans can be stopped.
+    pub fn scanning_usage_dir(&self) -> Option<&Path> {
+        match (self.mode, &self.usage) {
+            (Mode::Usage, Some(usage)) if usage.state == ScanState::Running => Some(&usage.dir),
+            _ => None,
+        }
+    }
+
+    pub fn start_usage(&mut self, dir: PathBuf) {
+        self.usage = Some(DiskUsage::new(dir));
+    }
+
+    pub fn update_usage(&mut self, dir: &Path, update: UsageUpdate) {
+        if let Some(usage) = self.usage.as_mut().filter(|usage| usage.dir == dir) {
+            usage.apply(update);
+        }
+    }
+
+    // Leaves Select, Filter, Prompt and Projects mode and moves to `path`, e.g. when the current directory vanished.
+    pub fn go_to(&mut self, path: PathBuf) -> Effect {
+        if matches!(
+            self.mode,
+            Mode::Select | Mode::Filter | Mode::Prompt | Mode::Projects
+        ) {
+            self.mode = Mode::Normal;
+        }
+        self.prefix.clear();
+        self.current_page = None;
+        self.cursor_index = None;
+        self.change_dir(path)
+    }
+
+    // Opens Projects mode with the repositories found below `dir`.
+    pub fn show_projects(&mut self, dir: PathBuf, projects: Vec<PathBuf>) {
+        self.mode = Mode::Projects;
+        self.projects_dir = dir;
+        self.projects = projects;
+        self.prefix.clear();
+        self.current_page = None;
+        self.cursor_index = None;
+    }
+
+    // Undoes the last `Effect::ChangeDir`, for directories the caller could not read.
+    pub fn revert(&mut self) {
+        if let Some(location) = self.previous.take() {
+            self.current_dir = location.dir;
+            self.history = location.history;
+            self.tree = location.tree;
+            self.usage = location.usage;
+            self.set_filter(location.filter);
+        }
+
+        if let Some(prompt) = self.previous_prompt.take() {
+            self.mode = Mode::Prompt;
+            self.prompt = prompt;
+        }
+    }
+
+    pub fn handle(&mut self, action: Action) -> Effect {
+        // Everything typed goes into the filter or the prompt, including `~`.
+        if !matches!(action, Action::Control(_)) {
+            match self.mode {
+                Mode::Filter => return self.handle_filter(action),
+                Mode::Prompt => return self.handle_prompt(action),
+                _ => {}
+            }
+        }
+
+        match action {
+            Action::Control('s') => {
+                self.show_hidden = !self.show_hidden;
+                self.cursor_index = None;
+                self.apply_filters();
+                Effect::Redraw
+            }
+            Action::Control('d') => {
+                self.long_view = !self.long_view;
+                Effect::Redraw
+            }
+            Action::Control('p') => {
+                self.column_view = !self.column_view;
+                Effect::Redraw
+            }
+            Action::Control('g') => Effect::FindRoot,
+            Action::Control('y') => {
+                Effect::Extract(self.highlighted_dir().unwrap_or(self.current_dir.clone()))
+            }
+            Action::Control('a') => match self.mode {
+                Mode::Projects => {
+                    self.leave_projects();
+                    Effect::Redraw
+                }
+                _ => Effect::FindProjects,
+            },
+            Action::Control('u') => {
+                self.mode = match self.mode {
+                    Mode::Normal => Mode::Usage,
+                    Mode::Usage => Mode::Normal,
+                    _ => return Effect::None,
+                };
+                self.prefix.clear();
+                self.cursor_index = None;
+                self.usage = None;
+                Effect::Redraw
+            }
+            Action::Control('r') => {
+                self.mode = match self.mode {
+                    Mode::Normal => Mode::Tree,
+                    Mode::Tree => Mode::Normal,
+                    Mode::Select | Mode::Filter | Mode::Prompt | Mode::Projects | Mode::Usage => {
+                        return Effect::None;
+                    }
+                };
+                self.prefix.clear();
+                self.cursor_index = None;
+                self.tree.clear();
+                Effect::Redraw
+            }
+            Action::Char('~') => match self.env.home_dir.clone() {
+                Some(home_dir) => self.go_to(home_dir),
+                None => Effect::None,
+            },
+            _ => match self.mode {
+                Mode::Normal => self.handle_normal(action),
+                Mode::Select => self.handle_select(action),
+                Mode::Tree => self.handle_tree(action),
+                Mode::Filter => self.handle_filter(action),
+                Mode::Prompt => self.handle_prompt(action),
+                Mode::Projects => self.handle_projects(action),
+                Mode::Usage => self.handle_usage(action),
+            },
+        }
+    }
+
+    fn handle_normal(&mut self, action: Action) -> Effect {
+        match action {
+            Action::Char('/') => {
+                self.mode = Mode::Filter;
+                self.cursor_index = None;
+                return Effect::Redraw;
+            }
+            Action::Char(':') | Action::Control('l') => {
+                self.mode = Mode::Prompt;
+                self.prompt.clear();
+                self.prompt_candidates.clear();
+                self.prompt_history_index = None;
+                return Effect::Redraw;
+            }
+            Action::Esc if !self.filter.is_empty() => {
+                self.set_filter(String::new());
+                return Effect::Redraw;
+            }
+            _ => {}
+        }
+
+        if self.hints {
+            return self.handle_labels(self.dirs.clone(), action);
+        }
+
+        match action {
+            Action::Char(c) => self.jump(c),
+            Action::Enter => {
+                let index = match self.cursor_index {
+                    Some(i) => i,
+                    None => return Effect::Exit(Some(self.current_dir.clone())),
+                };
+
+                let char_map = build_char_map(&self.dirs);
+                match char_map.keys().nth(index) {
+                    Some(&c) => self.jump(c),
+                    None => Effect::None,
+                }
+            }
+            Action::Tab => {
+                if self.dirs.is_empty() {
+                    return Effect::None;
+                }
+
+                let char_map = build_char_map(&self.dirs);
+                self.cursor_index = match self.cursor_index {
+                    Some(i) => Some((i + 1) % char_map.len()),
+                    None => Some(0),
+                };
+
+                Effect::Redraw
+            }
+            Action::Control(_) => Effect::None,
+            _ => self.handle_history(action),
+        }
+    }
+
+    // Backspace, Esc and the history keys, shared by all modes but Select.
+    fn handle_history(&mut self, action: Action) -> Effect {
+        match action {
+            Action::Backspace => match self.current_dir.parent() {
+                Some(parent) => {
+                    self.cursor_index = None;
+                    self.change_dir(parent.to_path_buf())
+                }
+                None => Effect::None,
+            },
+            Action::Esc => Effect::Exit(None),
+            Action::Up => {
+                self.save_location();
+                match self.history.go_up().cloned() {
+                    Some(path) => {
+                        self.cursor_index = None;
+                        self.forget_dir_state();
+                        self.current_dir = path.clone();
+                        Effect::ChangeDir(path)
+                    }
+                    None => Effect::None,
+                }
+            }
+            Action::Down => {
+                self.save_location();
+                match self.history.go_down().cloned() {
+                    Some(path) => {
+                        self.cursor_index = None;
+                        self.forget_dir_state();
+                        self.current_dir = path.clone();
+                        Effect::ChangeDir(path)
+                    }
+                    None => Effect::None,
+                }
+            }
+            _ => Effect::None,
+        }
+    }
+
+    fn handle_select(&mut self, action: Action) -> Effect {
+        let filtered_dirs = starts_with(&self.dirs, &self.prefix);
+        let max_page = self.paging.max_page(filtered_dirs.len());
+
+        match action {
+            Action::Control(c) => {
+                self.cursor_index = Some(0);
+
+                match c {
+                    'f' => {
+                        self.current_page = self.current_page.map(|p| (p + 1).min(max_page));
+                    }
+                    'b' => {
+                        self.current_page = self.current_page.map(|p| p.saturating_sub(1));
+                    }
+                    'n' => {
+                        self.current_page = self
+                            .current_page
+                            .map(|p| if p + 1 > max_page { 0 } else { p + 1 });
+                    }
+                    _ => return Effect::None,
+                }
+
+                Effect::Redraw
+            }
+            Action::Char(c) => {
+                self.cursor_index = Some(0);
+
+                // Keys past the page size are typed into the prefix like any other key.
+                let key = self
+                    .keybinds
+                    .chars()
+                    .take(self.paging.size())
+                    .position(|k| k == c);
+
+                let index = match key {
+                    Some(i) => self.paging.index(self.page(), i),
+                    None => {
+                        self.prefix.push(c);
+                        self.current_page = Some(0);
+                        return Effect::Redraw;
+                    }
+                };
+
+                match filtered_dirs.get(index) {
+                    Some(dir) => self.select(dir.clone()),
+                    None => Effect::None,
+                }
+            }
+            Action::Backspace => {
+                if self.prefix.chars().count() > 1 {
+                    self.prefix.pop();
+                    self.current_page = Some(0);
+                    self.cursor_index = Some(0);
+                } else {
+                    self.mode = Mode::Normal;
+                    self.current_page = None;
+                    self.cursor_index = None;
+                }
+                Effect::Redraw
+            }
+            Action::Esc => {
+                self.prefix.clear();
+                self.current_page = Some(0);
+                self.cursor_index = None;
+                self.mode = Mode::Normal;
+                Effect::Redraw
+            }
+            Action::Enter => {
+                let index = self
+                    .paging
+                    .index(self.page(), self.cursor_index.unwrap_or(0));
+
+                match filtered_dirs.get(index) {
+                    Some(dir) => self.select(dir.clone()),
+                    None => Effect::None,
+                }
+            }
+            Action::Tab => {
+                let on_page = self.paging.range(self.page(), filtered_dirs.len()).len();
+
+                if on_page == 0 {
+                    return Effect::None;
+                }
+
+                self.cursor_index = Some((self.cursor_index.unwrap_or(0) + 1) % on_page);
+                Effect::Redraw
+            }
+            Action::Up | Action::Down => Effect::None,
+        }
+    }
+
+    fn handle_filter(&mut self, action: Action) -> Effect {
+        match action {
+            Action::Char(c) => {
+                let mut filter = self.filter.clone();
+                filter.push(c);
+                self.set_filter(filter);
+                Effect::Redraw
+            }
+            Action::Backspace => {
+                let mut filter = self.filter.clone();
+                if filter.pop().is_none() {
+                    self.mode = Mode::Normal;
+                }
+                self.set_filter(filter);
+                Effect::Redraw
+            }
+            Action::Esc => {
+                self.mode = Mode::Normal;
+                self.set_filter(String::new());
+                Effect::Redraw
+            }
+            Action::Control('w') => {
+                let mut filter = self.filter.clone();
+                delete_word(&mut filter);
+                self.set_filter(filter);
+                Effect::Redraw
+            }
+            // Enters the only matching directory, otherwise the filter stays and the
+            // remaining directories can be reached with their keys.
+            Action::Enter => {
+                self.mode = Mode::Normal;
+                match self.dirs.as_slice() {
+                    [dir] => {
+                        let dir = dir.clone();
+                        self.change_dir(dir)
+                    }
+                    _ => Effect::Redraw,
+                }
+            }
+            Action::Tab | Action::Up | Action::Down | Action::Control(_) => Effect::None,
+        }
+    }
+
+    fn handle_prompt(&mut self, action: Action) -> Effect {
+        self.prompt_candidates.clear();
+
+        match action {
+            Action::Char(c) => {
+                self.prompt.push(c);
+                Effect::Redraw
+            }
+            Action::Backspace => {
+                if self.prompt.pop().is_none() {
+                    self.mode = Mode::Normal;
+                }
+                Effect::Redraw
+            }
+            Action::Control('w') => {
+                delete_word(&mut self.prompt);
+                Effect::Redraw
+            }
+            Action::Esc | Action::Control(_) => {
+                self.mode = Mode::Normal;
+                Effect::Redraw
+            }
+            Action::Tab => Effect::Complete(self.prompt.clone()),
+            Action::Up | Action::Down => {
+                if self.prompt_history.is_empty() {
+                    return Effect::None;
+                }
+
+                let last = self.prompt_history.len() - 1;
+                let index = match (action, self.prompt_history_index) {
+                    (Action::Up, None) => Some(last),
+                    (Action::Up, Some(i)) => Some(i.saturating_sub(1)),
+                    (_, Some(i)) if i < last => Some(i + 1),
+                    _ => None,
+                };
+
+                self.prompt_history_index = index;
+                self.prompt = index
+                    .map(|i| self.prompt_history[i].clone())
+                    .unwrap_or_default();
+                Effect::Redraw
+            }
+            Action::Enter => {
+                self.mode = Mode::Normal;
+                let text = std::mem::take(&mut self.prompt);
+                if text.is_empty() {
+                    return Effect::Redraw;
+                }
+
+                self.prompt_history.retain(|p| p != &text);
+                self.prompt_history.push(text.clone());
+
+                let path = expand(&text, &self.current_dir, &self.env);
+                self.cursor_index = None;
+                let effect = self.change_dir(path);
+                self.previous_prompt = Some(text);
+                effect
+            }
+        }
+    }
+
+    pub fn set_completion(&mut self, completion: Completion) {
+        self.prompt = completion.text;
+        self.prompt_candidates = completion.candidates;
+    }
+
+    fn handle_tree(&mut self, action: Action) -> Effect {
+        let rows = self.tree_rows();
+
+        match action {
+            Action::Char(' ') => match self.cursor_index.and_then(|i| rows.get(i)) {
+                Some(row) => {
+                    self.tree.toggle(&row.path);
+                    Effect::Redraw
+                }
+                None => Effect::None,
+            },
+            _ => self.handle_labels(rows.into_iter().map(|row| row.path).collect(), action),
+        }
+    }
+
+    fn handle_projects(&mut self, action: Action) -> Effect {
+        match action {
+            Action::Backspace | Action::Esc if self.prefix.is_empty() => {
+                self.leave_projects();
+                Effect::Redraw
+            }
+            // The history belongs to the directory behind the list.
+            Action::Up | Action::Down => Effect::None,
+            _ => {
+                let effect = self.handle_labels(self.projects.clone(), action);
+                if let Effect::ChangeDir(_) = effect {
+                    self.mode = Mode::Normal;
+                }
+                effect
+            }
+        }
+    }
+
+    // Esc stops a running scan first, the sizes found so far stay on screen. Labels stay
+    // with their directory while sizes arrive, the cursor walks the rows largest first.
+    fn handle_usage(&mut self, action: Action) -> Effect {
+        match action {
+            Action::Esc if self.prefix.is_empty() => {
+                match self.usage.as_mut() {
+                    Some(usage) if usage.state == ScanState::Running => {
+                        usage.state = ScanState::Cancelled;
+                    }
+                    _ => {
+                        self.mode = Mode::Normal;
+                        self.cursor_index = None;
+                        self.usage = None;
+                    }
+                }
+                Effect::Redraw
+            }
+            // Labels go down the rows as they are drawn, largest first.
+            _ => self.handle_labels(self.usage_dirs(), action),
+        }
+    }
+
+    fn leave_projects(&mut self) {
+        self.mode = Mode::Normal;
+        self.prefix.clear();
+        self.cursor_index = None;
+    }
+
+    // Tree mode, Projects mode and Normal mode with hints, every directory in `paths` has a hint label.
+    fn handle_labels(&mut self, paths: Vec<PathBuf>, action: Action) -> Effect {
+        match action {
+            Action::Char(c) => {
+                self.prefix.push(c);
+                let labels = hint_labels(paths.len(), &self.hint_alphabet);
+
+                if let Some(i) = labels.iter().position(|label| label == &self.prefix) {
+                    self.prefix.clear();
+                    self.cursor_index = None;
+                    return self.change_dir(paths[i].clone());
+                }
+
+                if !labels.iter().any(|label| label.starts_with(&self.prefix)) {
+                    self.prefix.clear();
+                }
+                Effect::Redraw
+            }
+            Action::Backspace if !self.prefix.is_empty() => {
+                self.prefix.pop();
+                Effect::Redraw
+            }
+            Action::Esc if !self.prefix.is_empty() => {
+                self.prefix.clear();
+                Effect::Redraw
+            }
+            Action::Enter => match self.cursor_index.and_then(|i| paths.get(i)) {
+                Some(path) => {
+                    self.cursor_index = None;
+                    self.change_dir(path.clone())
+                }
+                None => Effect::Exit(Some(self.current_dir.clone())),
+            },
+            Action::Tab => {
+                if paths.is_empty() {
+                    return Effect::None;
+                }
+
+                self.cursor_index = match self.cursor_index {
+                    Some(i) => Some((i + 1) % paths.len()),
+                    None => Some(0),
+                };
+                Effect::Redraw
+            }
+            // Moving through the file system works like in Normal mode.
+            Action::Backspace | Action::Esc | Action::Up | Action::Down => {
+                self.handle_history(action)
+            }
+            Action::Control(_) => Effect::None,
+        }
+    }
+
+    // Enters the only directory starting with `c` or opens Select mode for all of them.
+    fn jump(&mut self, c: char) -> Effect {
+        self.prefix.clear();
+        let filtered_dirs = starts_with(&self.dirs, &c.to_string());
+
+        if filtered_dirs.is_empty() {
+            return Effect::None;
+        }
+
+        if filtered_dirs.len() > 1 {
+            self.mode = Mode::Select;
+            self.current_page = Some(0);
+            self.cursor_index = Some(0);
+            self.prefix = c.to_string();
+            return Effect::Redraw;
+        }
+
+        self.cursor_index = None;
+        self.change_dir(filtered_dirs[0].clone())
+    }
+
+    fn select(&mut self, dir: PathBuf) -> Effect {
+        self.cursor_index = None;
+        self.mode = Mode::Normal;
+        self.change_dir(dir)
+    }
+
+    fn change_dir(&mut self, path: PathBuf) -> Effect {
+        self.save_location();
+        self.forget_dir_state();
+        self.history.push(&path);
+        self.current_dir = path.clone();
+        Effect::ChangeDir(path)
+    }
+
+    // Tree expansion, sizes and the filter belong to the directory that is left.
+    fn forget_dir_state(&mut self) {
+        self.tree.clear();
+        self.usage = None;
+        if !self.sticky_filter {
+            self.filter.clear();
+        }
+    }
+
+    fn save_location(&mut self) {
+        self.previous = Some(Location {
+            dir: self.current_dir.clone(),
+            history: self.history.clone(),
+            filter: self.filter.clone(),
+            tree: self.tree.clone(),
+            usage: self.usage.clone(),
+        });
+        self.previous_prompt = None;
+    }
+
+    fn apply_filters(&mut self) {
+        if self.show_hidden {
+            self.dirs = self.all_dirs.clone();
+            self.files = self.all_files.clone();
+        } else {
+            self.dirs = filter_hidden(&self.all_dirs);
+            self.files = filter_hidden(&self.all_files);
+        }
+
+        if !self.filter.is_empty() {
+            self.dirs = filter_paths(&self.dirs, &self.filter, self.filter_kind);
+            self.files = filter_paths(&self.files, &self.filter, self.filter_kind);
+        }
+    }
+
+    fn set_filter(&mut self, filter: String) {
+        self.filter = filter;
+        self.cursor_index = None;
+        self.apply_filters();
+    }
+
+    fn page(&self) -> usize {
+        self.current_page.unwrap_or(0)
+    }
+}
+
+// Removes the last word like <C-w> in a shell, path components count as words.
+fn delete_word(text: &mut String) {
+    let is_separator = |c: char| c == '/' || c.is_whitespace();
+
+    while text.ends_with(is_separator) {
+        text.pop();
+    }
+    while !text.is_empty() && !text.ends_with(is_separator) {
+        text.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn navigator(dirs: &[&str]) -> Navigator {
+        let env = Env {
+            home_dir: Some(PathBuf::from("/home/me")),
+            vars: HashMap::from([(String::from("TOP"), String::from("/top"))]),
+        };
+        let mut navigator = Navigator::new(
+            PathBuf::from("/work"),
+            env,
+            String::from("1234567890"),
+            true,
+        );
+        navigator.set_entries(dirs.iter().map(|d| path(d)).collect(), Vec::new());
+        navigator
+    }
+
+    fn path(name: &str) -> PathBuf {
+        Path::new("/work").join(name)
+    }
+
+    fn type_keys(navigator: &mut Navigator, keys: &str) -> Effect {
+        keys.chars()
+            .map(|c| navigator.handle(Action::Char(c)))
+            .last()
+            .unwrap_or(Effect::None)
+    }
+
+    #[test]
+    fn jump_enters_the_only_directory_with_the_letter() {
+        let mut navigator = navigator(&["alpha", "beta", "bravo"]);
+
+        assert_eq!(navigator.handle(Action::Char('z')), Effect::None);
+        assert_eq!(
+            navigator.handle(Action::Char('a')),
+            Effect::ChangeDir(path("alpha"))
+        );
+        assert_eq!(navigator.current_dir, path("alpha"));
+    }
+
+    #[test]
+    fn jump_to_a_shared_letter_opens_select_mode() {
+        let mut navigator = navigator(&["alpha", "beta", "bravo"]);
+
+        assert_eq!(navigator.handle(Action::Char('b')), Effect::Redraw);
+        assert!(matches!(navigator.mode, Mode::Select));
+        assert_eq!(navigator.prefix, "b");
+        assert_eq!(
+            navigator.handle(Action::Char('2')),
+            Effect::ChangeDir(path("bravo"))
+        );
+        assert!(matches!(navigator.mode, Mode::Normal));
+    }
+
+    #[test]
+    fn select_keys_are_relative_to_the_page() {
+        let mut navigator = navigator(&["b0", "b1", "b2", "b3", "b4"]);
+        navigator.set_paging(Paging::new(10, Some(2)));
+
+        navigator.handle(Action::Char('b'));
+        assert_eq!(navigator.handle(Action::Control('f')), Effect::Redraw);
+        assert_eq!(navigator.current_page, Some(1));
+
+        // The last page stops paging forward, wrapping starts over.
+        navigator.handle(Action::Control('f'));
+        navigator.handle(Action::Control('f'));
+        assert_eq!(navigator.current_page, Some(2));
+        navigator.handle(Action::Control('n'));
+        assert_eq!(navigator.current_page, Some(0));
+        navigator.handle(Action::Control('n'));
+        navigator.handle(Action::Control('b'));
+        assert_eq!(navigator.current_page, Some(0));
+
+        navigator.handle(Action::Control('f'));
+        assert_eq!(
+            navigator.handle(Action::Char('2')),
+            Effect::ChangeDir(path("b3"))
+        );
+    }
+
+    #[test]
+    fn select_keys_past_the_page_are_typed_into_the_prefix() {
+        let mut navigator = navigator(&["b0", "b1", "b2"]);
+        navigator.set_paging(Paging::new(10, Some(2)));
+
+        navigator.handle(Action::Char('b'));
+        assert_eq!(navigator.handle(Action::Char('3')), Effect::Redraw);
+        assert_eq!(navigator.prefix, "b3");
+    }
+
+    #[test]
+    fn tab_moves_the_cursor_and_enter_follows_it() {
+        let mut navigator = navigator(&["alpha", "beta"]);
+
+        assert_eq!(
+            navigator.handle(Action::Enter),
+            Effect::Exit(Some(path("")))
+        );
+
+        navigator.handle(Action::Tab);
+        assert_eq!(navigator.cursor_index, Some(0));
+        navigator.handle(Action::Tab);
+        navigator.handle(Action::Tab);
+        assert_eq!(navigator.cursor_index, Some(0));
+        navigator.handle(Action::Tab);
+        assert_eq!(navigator.highlighted_dir(), Some(path("beta")));
+        assert_eq!(
+            navigator.handle(Action::Enter),
+            Effect::ChangeDir(path("beta"))
+        );
+    }
+
+    #[test]
+    fn hint_labels_enter_directories() {
+        let mut navigator = navigator(&["a", "b", "c"]);
+        navigator.hints = true;
+        navigator.hint_alphabet = vec!['a', 's'];
+        assert_eq!(navigator.hint_labels(), ["a", "sa", "ss"]);
+
+        // Keys that no label starts with start over.
+        assert_eq!(type_keys(&mut navigator, "sx"), Effect::Redraw);
+        assert_eq!(navigator.prefix, "");
+
+        assert_eq!(type_keys(&mut navigator, "s"), Effect::Redraw);
+        assert_eq!(navigator.prefix, "s");
+        assert_eq!(
+            navigator.handle(Action::Char('s')),
+            Effect::ChangeDir(path("c"))
+        );
+        assert_eq!(navigator.prefix, "");
+    }
+
+    #[test]
+    fn hint_labels_follow_the_cursor() {
+        let mut navigator = navigator(&["a", "b", "c"]);
+        navigator.hints = true;
+
+        navigator.handle(Action::Tab);
+        navigator.handle(Action::Tab);
+        assert_eq!(
+            navigator.handle(Action::Enter),
+            Effect::ChangeDir(path("b"))
+        );
+    }
+
+    #[test]
+    fn filter_enter_opens_the_only_match() {
+        let mut navigator = navigator(&["alpha", "beta", "gamma"]);
+
+        navigator.handle(Action::Char('/'));
+        assert!(matches!(navigator.mode, Mode::Filter));
+        type_keys(&mut navigator, "ph");
+        assert_eq!(navigator.dirs(), [path("alpha")]);
+
+        assert_eq!(
+            navigator.handle(Action::Enter),
+            Effect::ChangeDir(path("alpha"))
+        );
+        assert!(matches!(navigator.mode, Mode::Normal));
+        assert_eq!(navigator.filter, "");
+    }
+
+    #[test]
+    fn filter_enter_keeps_several_matches() {
+        let mut navigator = navigator(&["alpha", "beta", "delta"]);
+
+        navigator.handle(Action::Char('/'));
+        type_keys(&mut navigator, "ta");
+        assert_eq!(navigator.handle(Action::Enter), Effect::Redraw);
+        assert!(matches!(navigator.mode, Mode::Normal));
+        assert_eq!(navigator.dirs(), [path("beta"), path("delta")]);
+        assert_eq!(navigator.filter, "ta");
+    }
+
+    #[test]
+    fn control_w_deletes_a_word_while_typing() {
+        let mut navigator = navigator(&["alpha"]);
+
+        navigator.handle(Action::Char(':'));
+        type_keys(&mut navigator, "~/src/twig");
+        navigator.handle(Action::Control('w'));
+        assert_eq!(navigator.prompt, "~/src/");
+        navigator.handle(Action::Control('w'));
+        assert_eq!(navigator.prompt, "~/");
+        assert!(matches!(navigator.mode, Mode::Prompt));
+
+        navigator.handle(Action::Esc);
+        navigator.handle(Action::Char('/'));
+        type_keys(&mut navigator, "al ph");
+        navigator.handle(Action::Control('w'));
+        assert_eq!(navigator.filter, "al ");
+    }
+
+    #[test]
+    fn prompt_browses_the_history() {
+        let mut navigator = navigator(&[]);
+        navigator.prompt_history = vec![String::from("/a"), String::from("/b")];
+
+        navigator.handle(Action::Char(':'));
+        navigator.handle(Action::Up);
+        assert_eq!(navigator.prompt, "/b");
+        navigator.handle(Action::Up);
+        navigator.handle(Action::Up);
+        assert_eq!(navigator.prompt, "/a");
+        navigator.handle(Action::Down);
+        assert_eq!(navigator.prompt, "/b");
+        navigator.handle(Action::Down);
+        assert_eq!(navigator.prompt, "");
+    }
+
+    #[test]
+    fn prompt_enter_changes_dir_and_revert_reopens_it() {
+        let mut navigator = navigator(&[]);
+        navigator.prompt_history = vec![String::from("sub"), String::from("/a")];
+
+        navigator.handle(Action::Char(':'));
+        type_keys(&mut navigator, "sub");
+        assert_eq!(
+            navigator.handle(Action::Enter),
+            Effect::ChangeDir(path("sub"))
+        );
+        navigator.revert();
+        assert_eq!(navigator.current_dir, path(""));
+        assert!(matches!(navigator.mode, Mode::Prompt));
+        assert_eq!(navigator.prompt, "sub");
+
+        navigator.prompt = String::from("$TOP/x");
+        assert_eq!(
+            navigator.handle(Action::Enter),
+            Effect::ChangeDir(PathBuf::from("/top/x"))
+        );
+
+        // The most recent path is last, without duplicates.
+        navigator.handle(Action::Char(':'));
+        navigator.prompt = String::from("~/../a");
+        assert_eq!(
+            navigator.handle(Action::Enter),
+            Effect::ChangeDir(PathBuf::from("/home/a"))
+        );
+        assert_eq!(navigator.prompt_history, ["/a", "sub", "$TOP/x", "~/../a"]);
+    }
+
+    #[test]
+    fn revert_restores_the_history() {
+        let mut navigator = navigator(&["alpha"]);
+
+        navigator.handle(Action::Char('a'));
+        navigator.revert();
+        assert_eq!(navigator.current_dir, path(""));
+        assert_eq!(navigator.handle(Action::Up), Effect::None);
+    }
+
+    #[test]
+    fn usage_cursor_walks_the_largest_first() {
+        let mut navigator = navigator(&["alpha", "beta", "gamma"]);
+
+        navigator.handle(Action::Control('u'));
+        navigator.start_usage(path(""));
+        for (name, size) in [("alpha", 1), ("beta", 30), ("gamma", 20)] {
+            navigator.update_usage(&path(""), UsageUpdate::Size(path(name), size));
+        }
+        assert_eq!(
+            navigator.usage_dirs(),
+            [path("beta"), path("gamma"), path("alpha")]
+        );
+
+        navigator.handle(Action::Tab);
+        navigator.handle(Action::Tab);
+        assert_eq!(navigator.highlighted_dir(), Some(path("gamma")));
+        assert_eq!(
+            navigator.handle(Action::Enter),
+            Effect::ChangeDir(path("gamma"))
+        );
+    }
+
+    #[test]
+    fn usage_labels_follow_the_size_order() {
+        let mut navigator = navigator(&["alpha", "beta", "gamma"]);
+
+        navigator.handle(Action::Control('u'));
+        navigator.start_usage(path(""));
+        navigator.update_usage(&path(""), UsageUpdate::Size(path("gamma"), 10));
+        navigator.update_usage(&path(""), UsageUpdate::Size(path("alpha"), 5));
+        assert_eq!(
+            navigator.usage_dirs(),
+            [path("gamma"), path("alpha"), path("beta")]
+        );
+
+        navigator.handle(Action::Tab);
+        assert_eq!(navigator.highlighted_dir(), Some(path("gamma")));
+        assert_eq!(
+            navigator.handle(Action::Char('s')),
+            Effect::ChangeDir(path("alpha"))
+        );
+    }
+
+    #[test]
+    fn revert_restores_the_filter_and_sizes() {
+        let mut navigator = navigator(&["alpha", "beta", "delta"]);
+
+        navigator.handle(Action::Char('/'));
+        type_keys(&mut navigator, "ta");
+        navigator.handle(Action::Enter);
+        navigator.handle(Action::Char('b'));
+        assert_eq!(navigator.filter, "");
+        navigator.revert();
+        assert_eq!(navigator.filter, "ta");
+        assert_eq!(navigator.dirs(), [path("beta"), path("delta")]);
+
+        navigator.handle(Action::Control('u'));
+        navigator.start_usage(path(""));
+        navigator.update_usage(&path(""), UsageUpdate::Size(path("beta"), 10));
+        navigator.handle(Action::Char('a'));
+        assert!(navigator.usage.is_none());
+        navigator.revert();
+        let usage = navigator.usage.as_ref().expect("sizes are restored");
+        assert_eq!(usage.sizes.get(&path("beta")), Some(&10));
+    }
+}
@@ -0,0 +1,67 @@
+use std::ops::Range;
+
+// Splits the directories of Select mode into pages, every directory on a page gets one
+// of the select keys. All page math goes through here.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Paging {
+    size: usize,
+}
+
+impl Paging {
+    // One page per select key, unless fewer `rows` fit on the screen.
+    pub fn new(keys: usize, rows: Option<usize>) -> Self {
+        Paging {
+            size: keys.min(rows.unwrap_or(keys)).max(1),
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    // The last page for `n` directories, 0 if there are none.
+    pub fn max_page(&self, n: usize) -> usize {
+        if n == 0 { 0 } else { (n - 1) / self.size }
+    }
+
+    // Indices of the directories on `page`, empty past the last page.
+    pub fn range(&self, page: usize, n: usize) -> Range<usize> {
+        let start = (page * self.size).min(n);
+        start..(start + self.size).min(n)
+    }
+
+    // Index of the `i`-th directory on `page` among all of them.
+    pub fn index(&self, page: usize, i: usize) -> usize {
+        page * self.size + i
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pages_are_limited_by_keys_and_rows() {
+        assert_eq!(Paging::new(10, None).size(), 10);
+        assert_eq!(Paging::new(10, Some(3)).size(), 3);
+        assert_eq!(Paging::new(10, Some(0)).size(), 1);
+    }
+
+    #[test]
+    fn max_page() {
+        let paging = Paging::new(10, Some(2));
+        assert_eq!(paging.max_page(0), 0);
+        assert_eq!(paging.max_page(2), 0);
+        assert_eq!(paging.max_page(3), 1);
+        assert_eq!(paging.max_page(5), 2);
+    }
+
+    #[test]
+    fn range_and_index() {
+        let paging = Paging::new(10, Some(2));
+        assert_eq!(paging.range(0, 5), 0..2);
+        assert_eq!(paging.range(2, 5), 4..5);
+        assert_eq!(paging.range(3, 5), 5..5);
+        assert_eq!(paging.index(2, 1), 5);
+    }
+}
@@ -0,0 +1,132 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::dir_util::{filter_hidden, get_dirs_files};
+
+// `name` matches if the entry exists, `name:text` if the file also contains `text`.
+pub const DEFAULT_ROOT_MARKERS: [&str; 4] =
+    [".git", "Cargo.toml:[workspace]", "go.mod", "package.json"];
+
+// Levels below the projects directory searched for repositories.
+pub const DEFAULT_PROJECTS_DEPTH: usize = 3;
+
+// The nearest ancestor of `dir` with one of the markers. Starting in a root finds the
+// next one above it, so repeated jumps climb from a package to its workspace.
+pub fn find_root(dir: &Path, markers: &[String]) -> Option<PathBuf> {
+    let start = if is_root(dir, markers) {
+        dir.parent()?
+    } else {
+        dir
+    };
+
+    start
+        .ancestors()
+        .find(|ancestor| is_root(ancestor, markers))
+        .map(Path::to_path_buf)
+}
+
+fn is_root(dir: &Path, markers: &[String]) -> bool {
+    markers.iter().any(|marker| match marker.split_once(':') {
+        Some((name, text)) => {
+            fs::read_to_string(dir.join(name)).is_ok_and(|content| content.contains(text))
+        }
+        None => dir.join(marker).exists(),
+    })
+}
+
+// Git repositories below `dir`, sorted by path. Hidden directories and the insides
+// of repositories are not searched.
+pub fn find_repositories(dir: &Path, depth: usize) -> Vec<PathBuf> {
+    let mut repositories = Vec::new();
+    collect_repositories(dir, depth, &mut repositories);
+    repositories.sort();
+    repositories
+}
+
+fn collect_repositories(dir: &Path, depth: usize, repositories: &mut Vec<PathBuf>) {
+    if depth == 0 {
+        return;
+    }
+
+    let dirs = match get_dirs_files(dir) {
+        Ok((dirs, _)) => filter_hidden(&dirs),
+        Err(_) => return,
+    };
+
+    for dir in dirs {
+        if dir.join(".git").exists() {
+            repositories.push(dir);
+        } else {
+            collect_repositories(&dir, depth - 1, repositories);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProjectKind {
+    Rust,
+    Node,
+    Python,
+    Go,
+    Git,
+}
+
+impl ProjectKind {
+    const ALL: [ProjectKind; 5] = [
+        ProjectKind::Rust,
+        ProjectKind::Node,
+        ProjectKind::Python,
+        ProjectKind::Go,
+        ProjectKind::Git,
+    ];
+
+    // Entries directly inside a directory that make it a project of this kind.
+    fn markers(self) -> &'static [&'static str] {
+        match self {
+            ProjectKind::Rust => &["Cargo.toml"],
+            ProjectKind::Node => &["package.json"],
+            ProjectKind::Python => &["pyproject.toml", "setup.py", "requirements.txt"],
+            ProjectKind::Go => &["go.mod"],
+            ProjectKind::Git => &[".git"],
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ProjectKind::Rust => "rust",
+            ProjectKind::Node => "node",
+            ProjectKind::Python => "python",
+            ProjectKind::Go => "go",
+            ProjectKind::Git => "git",
+        }
+    }
+}
+
+// Kinds of project `dir` is, found by looking for marker files directly inside it.
+pub fn detect_projects(dir: &Path) -> Vec<ProjectKind> {
+    ProjectKind::ALL
+        .into_iter()
+        .filter(|kind| kind.markers().iter().any(|m| dir.join(m).exists()))
+        .collect()
+}
+
+// Remembers detected projects, so redrawing a listing does not look for markers again.
+#[derive(Debug, Default)]
+pub struct ProjectCache {
+    kinds: HashMap<PathBuf, Vec<ProjectKind>>,
+}
+
+impl ProjectCache {
+    pub fn get(&mut self, dir: &Path) -> &[ProjectKind] {
+        self.kinds
+            .entry(dir.to_path_buf())
+            .or_insert_with(|| detect_projects(dir))
+    }
+
+    pub fn clear(&mut self) {
+        self.kinds.clear();
+    }
+}
@@ -0,0 +1,295 @@
+use std::{
+    collections::HashMap,
+    env,
+    ffi::OsString,
+    fs, io,
+    path::{Component, Path, PathBuf},
+};
+
+use crate::dir_util::{escape_os_str, get_dirs_files};
+
+// Typed paths older than this are dropped from the history file.
+const HISTORY_LIMIT: usize = 100;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Completion {
+    pub text: String,
+    // Directories that still match when the text could not be completed to a single one.
+    pub candidates: Vec<String>,
+}
+
+// The home directory and variables paths are expanded with. The navigator gets them
+// from its caller, so it never reads the environment of the process itself.
+#[derive(Debug, Clone, Default)]
+pub struct Env {
+    pub home_dir: Option<PathBuf>,
+    pub vars: HashMap<String, String>,
+}
+
+impl Env {
+    // Variables that are not UTF-8 are left out, like unknown ones.
+    pub fn from_process() -> Self {
+        Env {
+            home_dir: dirs::home_dir(),
+            vars: env::vars_os()
+                .filter_map(|(name, value)| {
+                    Some((name.into_string().ok()?, value.into_string().ok()?))
+                })
+                .collect(),
+        }
+    }
+}
+
+// Resolves `~`, `$VAR` and `${VAR}` and relative paths. `.` and `..` are removed
+// without touching the file system, like `cd` does with logical paths.
+pub fn expand(input: &str, current_dir: &Path, env: &Env) -> PathBuf {
+    let input = expand_vars(input, &env.vars);
+
+    let path = match (input.strip_prefix('~'), env.home_dir.as_deref()) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => {
+            home.join(rest.trim_start_matches('/'))
+        }
+        _ => PathBuf::from(&input),
+    };
+
+    normalize(&current_dir.join(path))
+}
+
+// Unknown variables are left as they are.
+fn expand_vars(input: &str, vars: &HashMap<String, String>) -> String {
+    let mut result = String::new();
+    let mut rest = input;
+
+    while let Some(start) = rest.find('$') {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+
+        let (name, len) = match after.strip_prefix('{') {
+            Some(braced) => match braced.find('}') {
+                Some(end) => (&braced[..end], end + 2),
+                None => ("", 0),
+            },
+            None => {
+                let end = after
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .unwrap_or(after.len());
+                (&after[..end], end)
+            }
+        };
+
+        match vars.get(name) {
+            Some(value) => result.push_str(value),
+            None => result.push_str(&rest[start..start + 1 + len]),
+        }
+        rest = &after[len..];
+    }
+
+    result.push_str(rest);
+    result
+}
+
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            c => normalized.push(c),
+        }
+    }
+
+    normalized
+}
+
+// Completes the last part of `input` to the directories starting with it. Hidden
+// directories are only offered once the part starts with a dot. Names are matched
+// and inserted as they are, only the candidates are escaped for display.
+pub fn complete(input: &str, current_dir: &Path, env: &Env) -> Completion {
+    let (parent, prefix) = match input.rfind('/') {
+        Some(i) => (&input[..=i], &input[i + 1..]),
+        None => ("", input),
+    };
+
+    let dir = expand(parent, current_dir, env);
+    let names: Vec<OsString> = get_dirs_files(&dir)
+        .map(|(dirs, _)| dirs)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|d| d.file_name().map(|name| name.to_os_string()))
+        .filter(|name| name.as_encoded_bytes().starts_with(prefix.as_bytes()))
+        .filter(|name| prefix.starts_with('.') || !name.as_encoded_bytes().starts_with(b"."))
+        .collect();
+
+    let candidates = names.iter().map(|name| escape_os_str(name)).collect();
+
+    match names.as_slice() {
+        [] => Completion {
+            text: input.to_string(),
+            candidates: Vec::new(),
+        },
+        [name] => match name.to_str() {
+            Some(name) => Completion {
+                text: format!("{}{}/", parent, name),
+                candidates: Vec::new(),
+            },
+            // Names that are not UTF-8 cannot be typed, they are only shown.
+            None => Completion {
+                text: input.to_string(),
+                candidates,
+            },
+        },
+        _ => Completion {
+            text: format!("{}{}", parent, common_prefix(&names)),
+            candidates,
+        },
+    }
+}
+
+// Stops before the first byte that differs, or earlier to end on a whole character.
+fn common_prefix(names: &[OsString]) -> String {
+    let first = names[0].as_encoded_bytes();
+    let len = names[1..].iter().fold(first.len(), |len, name| {
+        first[..len]
+            .iter()
+            .zip(name.as_encoded_bytes())
+            .take_while(|(a, b)| a == b)
+            .count()
+    });
+
+    first[..len]
+        .utf8_chunks()
+        .next()
+        .map(|chunk| chunk.valid().to_string())
+        .unwrap_or_default()
+}
+
+// Typed paths are kept in `~/.local/share/twiggle/prompt_history`, oldest first.
+pub fn load_history() -> Vec<String> {
+    history_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .map(|content| content.lines().map(String::from).collect())
+        .unwrap_or_default()
+}
+
+pub fn save_history(history: &[String]) -> io::Result<()> {
+    let path = match history_path() {
+        Some(p) => p,
+        None => return Ok(()),
+    };
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let start = history.len().saturating_sub(HISTORY_LIMIT);
+    let mut content = history[start..].join("\n");
+    content.push('\n');
+    fs::write(path, content)
+}
+
+fn history_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("twiggle").join("prompt_history"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp_dir::TempDir;
+
+    fn env() -> Env {
+        Env {
+            home_dir: Some(PathBuf::from("/home/me")),
+            vars: HashMap::from([(String::from("PROJECTS"), String::from("/src"))]),
+        }
+    }
+
+    #[test]
+    fn expand_resolves_home_and_dots() {
+        let cwd = Path::new("/a/b");
+        let home = &env();
+
+        assert_eq!(expand("~", cwd, home), PathBuf::from("/home/me"));
+        assert_eq!(expand("~/x", cwd, home), PathBuf::from("/home/me/x"));
+        assert_eq!(expand("~x", cwd, home), PathBuf::from("/a/b/~x"));
+        assert_eq!(expand("../c/./d", cwd, home), PathBuf::from("/a/c/d"));
+        assert_eq!(expand("/e/..", cwd, home), PathBuf::from("/"));
+    }
+
+    #[test]
+    fn expand_resolves_variables() {
+        let cwd = Path::new("/a");
+        assert_eq!(expand("$PROJECTS/x", cwd, &env()), PathBuf::from("/src/x"));
+        assert_eq!(expand("${PROJECTS}x", cwd, &env()), PathBuf::from("/srcx"));
+    }
+
+    #[test]
+    fn expand_leaves_unknown_variables() {
+        let cwd = Path::new("/a");
+        assert_eq!(
+            expand("$UNSET/${UNSET}/$", cwd, &env()),
+            PathBuf::from("/a/$UNSET/${UNSET}/$")
+        );
+    }
+
+    #[test]
+    fn complete_extends_to_matching_directories() {
+        let temp = TempDir::new("complete");
+        let dir = temp.path();
+        for name in ["alpha", "alps", "beta", ".hidden", "beta/inner"] {
+            fs::create_dir_all(dir.join(name)).unwrap();
+        }
+        fs::write(dir.join("bfile"), "").unwrap();
+
+        let complete = |input: &str| complete(input, dir, &Env::default());
+
+        assert_eq!(complete("b").text, "beta/");
+        assert_eq!(complete("beta/").text, "beta/inner/");
+        assert_eq!(complete(".").text, ".hidden/");
+        assert_eq!(complete("x").text, "x");
+
+        let partial = complete("a");
+        assert_eq!(partial.text, "alp");
+        assert_eq!(partial.candidates, ["alpha", "alps"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn complete_inserts_raw_names() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+        let temp = TempDir::new("complete-raw");
+        let dir = temp.path();
+        fs::create_dir(dir.join("back\\slash")).unwrap();
+        fs::create_dir(dir.join("tab\there")).unwrap();
+        fs::create_dir(dir.join(OsStr::from_bytes(b"caf\xe9"))).unwrap();
+        fs::create_dir(dir.join("caf\u{e9}")).unwrap();
+        fs::create_dir(dir.join("caf\u{e8}")).unwrap();
+        fs::create_dir(dir.join(OsStr::from_bytes(b"n\xff"))).unwrap();
+
+        let complete = |input: &str| complete(input, dir, &Env::default());
+
+        let backslash = complete("ba").text;
+        assert_eq!(backslash, "back\\slash/");
+        assert!(expand(&backslash, dir, &Env::default()).is_dir());
+
+        let tab = complete("ta").text;
+        assert_eq!(tab, "tab\there/");
+        assert!(expand(&tab, dir, &Env::default()).is_dir());
+
+        // `é` and `è` share their first byte, the common prefix stops before it.
+        let mut all = complete("c");
+        all.candidates.sort();
+        assert_eq!(all.text, "caf");
+        assert_eq!(all.candidates, ["caf\\xe9", "caf\u{e8}", "caf\u{e9}"]);
+
+        assert_eq!(complete("caf\u{e9}").text, "caf\u{e9}/");
+
+        // The only match cannot be typed, it is shown instead.
+        let invalid = complete("n");
+        assert_eq!(invalid.text, "n");
+        assert_eq!(invalid.candidates, ["n\\xff"]);
+    }
+}
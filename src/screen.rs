@@ -57,6 +57,12 @@ impl Screen {
         Ok(())
     }
 
+    // Forgets the previous frame, e.g. after a child process wrote to the terminal.
+    pub fn reset(&mut self) {
+        self.previous_num_rows = 0;
+        self.line_lengths.clear();
+    }
+
     // Clears everything behind num_rows based on how many line_lengths there are.
     pub fn clear_rest(&mut self) -> std::io::Result<()> {
         let current_num_rows = self.calculate_num_rows()?;
@@ -0,0 +1,444 @@
+use crossterm::style::Color;
+use std::{collections::HashMap, env, io};
+
+use crate::config::ThemeConfig;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Theme {
+    // Text on top of colored headers, also used for the cursor.
+    pub header_text: Color,
+    pub path: Color,
+    pub history: Color,
+    pub directory: Color,
+    // Rows with several directories sharing a letter.
+    pub directory_group: Color,
+    pub key: Color,
+    pub select: Color,
+    pub file: Color,
+    pub broken_link: Color,
+    pub error: Color,
+    pub debug: Color,
+    // Icons take the color of their file type instead of the row color.
+    pub icon_colors: bool,
+    // Icon colors are converted to this depth when they are drawn.
+    pub depth: ColorDepth,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorDepth {
+    TrueColor,
+    Ansi256,
+    Ansi16,
+}
+
+impl Theme {
+    pub fn default_preset() -> Self {
+        Theme {
+            header_text: Color::Black,
+            path: Color::Blue,
+            history: Color::Blue,
+            directory: Color::DarkYellow,
+            directory_group: Color::White,
+            key: Color::White,
+            select: Color::DarkGreen,
+            file: Color::Magenta,
+            broken_link: Color::Red,
+            error: Color::Red,
+            debug: Color::Cyan,
+            icon_colors: true,
+            depth: ColorDepth::TrueColor,
+        }
+    }
+
+    // Everything is white, headers keep black text.
+    pub fn monochrome() -> Self {
+        Theme {
+            header_text: Color::Black,
+            path: Color::White,
+            history: Color::White,
+            directory: Color::White,
+            directory_group: Color::White,
+            key: Color::White,
+            select: Color::White,
+            file: Color::White,
+            broken_link: Color::White,
+            error: Color::White,
+            debug: Color::White,
+            icon_colors: false,
+            depth: ColorDepth::TrueColor,
+        }
+    }
+
+    pub fn preset(name: &str) -> Option<Self> {
+        let theme = match name {
+            "default" => Theme::default_preset(),
+            "light" => Theme {
+                header_text: Color::White,
+                path: Color::DarkBlue,
+                history: Color::DarkBlue,
+                directory: Color::DarkYellow,
+                directory_group: Color::Black,
+                key: Color::Black,
+                select: Color::DarkGreen,
+                file: Color::DarkMagenta,
+                broken_link: Color::DarkRed,
+                error: Color::DarkRed,
+                debug: Color::DarkCyan,
+                icon_colors: true,
+                depth: ColorDepth::TrueColor,
+            },
+            "high-contrast" => Theme {
+                header_text: Color::Black,
+                path: Color::White,
+                history: Color::White,
+                directory: Color::Yellow,
+                directory_group: Color::White,
+                key: Color::White,
+                select: Color::Green,
+                file: Color::Magenta,
+                broken_link: Color::Red,
+                error: Color::Red,
+                debug: Color::Cyan,
+                icon_colors: true,
+                depth: ColorDepth::TrueColor,
+            },
+            "solarized" => Theme {
+                header_text: rgb(0x002b36),
+                path: rgb(0x268bd2),
+                history: rgb(0x268bd2),
+                directory: rgb(0xb58900),
+                directory_group: rgb(0x93a1a1),
+                key: rgb(0xeee8d5),
+                select: rgb(0x859900),
+                file: rgb(0xd33682),
+                broken_link: rgb(0xdc322f),
+                error: rgb(0xdc322f),
+                debug: rgb(0x2aa198),
+                icon_colors: true,
+                depth: ColorDepth::TrueColor,
+            },
+            "catppuccin" => Theme {
+                header_text: rgb(0x1e1e2e),
+                path: rgb(0x89b4fa),
+                history: rgb(0x89b4fa),
+                directory: rgb(0xf9e2af),
+                directory_group: rgb(0xcdd6f4),
+                key: rgb(0xcdd6f4),
+                select: rgb(0xa6e3a1),
+                file: rgb(0xf5c2e7),
+                broken_link: rgb(0xf38ba8),
+                error: rgb(0xf38ba8),
+                debug: rgb(0x94e2d5),
+                icon_colors: true,
+                depth: ColorDepth::TrueColor,
+            },
+            _ => return None,
+        };
+
+        Some(theme)
+    }
+
+    // Looks up user themes first, they can extend a preset with `base`.
+    pub fn load(name: &str, user_themes: &HashMap<String, ThemeConfig>) -> io::Result<Self> {
+        let user_theme = match user_themes.get(name) {
+            Some(t) => t,
+            None => {
+                return Theme::preset(name).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("Unknown theme {}", name),
+                    )
+                });
+            }
+        };
+
+        let base = user_theme.base.as_deref().unwrap_or("default");
+        let mut theme = Theme::preset(base).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Unknown base theme {} in theme {}", base, name),
+            )
+        })?;
+
+        let fields = [
+            (&mut theme.header_text, &user_theme.header_text),
+            (&mut theme.path, &user_theme.path),
+            (&mut theme.history, &user_theme.history),
+            (&mut theme.directory, &user_theme.directory),
+            (&mut theme.directory_group, &user_theme.directory_group),
+            (&mut theme.key, &user_theme.key),
+            (&mut theme.select, &user_theme.select),
+            (&mut theme.file, &user_theme.file),
+            (&mut theme.broken_link, &user_theme.broken_link),
+            (&mut theme.error, &user_theme.error),
+            (&mut theme.debug, &user_theme.debug),
+        ];
+
+        if let Some(icon_colors) = user_theme.icon_colors {
+            theme.icon_colors = icon_colors;
+        }
+
+        for (color, value) in fields {
+            if let Some(value) = value {
+                *color = parse_color(value).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("Invalid color {} in theme {}", value, name),
+                    )
+                })?;
+            }
+        }
+
+        Ok(theme)
+    }
+
+    pub fn downgrade(self, depth: ColorDepth) -> Self {
+        let convert = |color: Color| downgrade_color(color, depth);
+
+        Theme {
+            header_text: convert(self.header_text),
+            path: convert(self.path),
+            history: convert(self.history),
+            directory: convert(self.directory),
+            directory_group: convert(self.directory_group),
+            key: convert(self.key),
+            select: convert(self.select),
+            file: convert(self.file),
+            broken_link: convert(self.broken_link),
+            error: convert(self.error),
+            debug: convert(self.debug),
+            icon_colors: self.icon_colors,
+            depth,
+        }
+    }
+
+    // The color of an icon, None keeps the color of its row.
+    pub fn icon_color(&self, color: Option<Color>) -> Option<Color> {
+        color
+            .filter(|_| self.icon_colors)
+            .map(|color| downgrade_color(color, self.depth))
+    }
+}
+
+impl ColorDepth {
+    pub fn detect() -> Self {
+        let colorterm = env::var("COLORTERM").unwrap_or_default();
+        if colorterm == "truecolor" || colorterm == "24bit" {
+            return ColorDepth::TrueColor;
+        }
+
+        let term = env::var("TERM").unwrap_or_default();
+        if term.contains("256color") {
+            ColorDepth::Ansi256
+        } else {
+            ColorDepth::Ansi16
+        }
+    }
+}
+
+// Accepts `#rrggbb`, an index of the 256 color palette like `208` and crossterm's color
+// names like `dark_yellow`.
+pub fn parse_color(value: &str) -> Option<Color> {
+    if let Some(hex) = value.strip_prefix('#') {
+        if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        return u32::from_str_radix(hex, 16).ok().map(rgb);
+    }
+
+    if value.chars().all(|c| c.is_ascii_digit()) {
+        return value.parse().ok().map(Color::AnsiValue);
+    }
+
+    Color::try_from(value).ok()
+}
+
+fn rgb(hex: u32) -> Color {
+    Color::Rgb {
+        r: (hex >> 16) as u8,
+        g: (hex >> 8) as u8,
+        b: hex as u8,
+    }
+}
+
+fn downgrade_color(color: Color, depth: ColorDepth) -> Color {
+    match (color, depth) {
+        (Color::Rgb { r, g, b }, ColorDepth::Ansi256) => Color::AnsiValue(rgb_to_ansi256(r, g, b)),
+        (Color::Rgb { r, g, b }, ColorDepth::Ansi16) => rgb_to_ansi16(r, g, b),
+        _ => color,
+    }
+}
+
+fn rgb_to_ansi256(r: u8, g: u8, b: u8) -> u8 {
+    // Grays have their own finer ramp from 232 to 255.
+    if r == g && g == b {
+        return match r {
+            0..8 => 16,
+            249.. => 231,
+            _ => 232 + ((r as u16 - 8) * 24 / 241) as u8,
+        };
+    }
+
+    let scale = |c: u8| (c as u16 * 5 / 255) as u8;
+    16 + 36 * scale(r) + 6 * scale(g) + scale(b)
+}
+
+// Picks by hue instead of distance, so pastel colors do not all end up gray.
+fn rgb_to_ansi16(r: u8, g: u8, b: u8) -> Color {
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+
+    if max - min < 40 {
+        return match max {
+            0..64 => Color::Black,
+            64..160 => Color::DarkGrey,
+            160..224 => Color::Grey,
+            _ => Color::White,
+        };
+    }
+
+    let (r, g, b) = (r as f32, g as f32, b as f32);
+    let (max, delta) = (max as f32, (max - min) as f32);
+
+    let hue = if max == r {
+        60.0 * ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / delta + 2.0)
+    } else {
+        60.0 * ((r - g) / delta + 4.0)
+    };
+
+    let bright = max > 200.0;
+    let pick = |bright_color, dark_color| if bright { bright_color } else { dark_color };
+
+    match hue as u32 {
+        0..30 | 330.. => pick(Color::Red, Color::DarkRed),
+        30..90 => pick(Color::Yellow, Color::DarkYellow),
+        90..150 => pick(Color::Green, Color::DarkGreen),
+        150..210 => pick(Color::Cyan, Color::DarkCyan),
+        210..270 => pick(Color::Blue, Color::DarkBlue),
+        _ => pick(Color::Magenta, Color::DarkMagenta),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_hex_indices_and_names() {
+        let cases = [
+            ("#ff8000", Some(rgb(0xff8000))),
+            ("#FF8000", Some(rgb(0xff8000))),
+            ("208", Some(Color::AnsiValue(208))),
+            ("0", Some(Color::AnsiValue(0))),
+            ("dark_yellow", Some(Color::DarkYellow)),
+            ("Red", Some(Color::Red)),
+        ];
+
+        for (value, color) in cases {
+            assert_eq!(parse_color(value), color, "{}", value);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_colors() {
+        for value in [
+            "", "#", "#fff", "#ff80000", "#+ff800", "#gg8000", "256", "-1", "orange",
+        ] {
+            assert_eq!(parse_color(value), None, "{}", value);
+        }
+    }
+
+    #[test]
+    fn truecolor_stays() {
+        let color = rgb(0xce422b);
+        assert_eq!(downgrade_color(color, ColorDepth::TrueColor), color);
+    }
+
+    #[test]
+    fn downgrades_to_the_256_color_cube_and_gray_ramp() {
+        let cases = [
+            (0x000000, 16),
+            (0xffffff, 231),
+            (0xff0000, 196),
+            (0x0000ff, 21),
+            (0xff8000, 208),
+            (0x808080, 243),
+            (0x121212, 232),
+        ];
+
+        for (hex, index) in cases {
+            assert_eq!(
+                downgrade_color(rgb(hex), ColorDepth::Ansi256),
+                Color::AnsiValue(index),
+                "{:06x}",
+                hex
+            );
+        }
+    }
+
+    #[test]
+    fn downgrades_to_16_colors_by_hue() {
+        let cases = [
+            (0xff0000, Color::Red),
+            (0x800000, Color::DarkRed),
+            (0xffd700, Color::Yellow),
+            (0x00ff00, Color::Green),
+            (0x00c0c0, Color::DarkCyan),
+            (0x4040ff, Color::Blue),
+            (0xa000a0, Color::DarkMagenta),
+            (0x202020, Color::Black),
+            (0x808080, Color::DarkGrey),
+            (0xc0c0c0, Color::Grey),
+            (0xf0f0f0, Color::White),
+        ];
+
+        for (hex, color) in cases {
+            assert_eq!(
+                downgrade_color(rgb(hex), ColorDepth::Ansi16),
+                color,
+                "{:06x}",
+                hex
+            );
+        }
+    }
+
+    #[test]
+    fn rust_stays_orange() {
+        let (_, color) = twiggle::icons::project_icon(twiggle::project::ProjectKind::Rust);
+        let color = color.unwrap();
+
+        assert_eq!(color, rgb(0xf74c00));
+        // 166 is #d75f00, the 16-color palette has no orange and red is closest.
+        assert_eq!(
+            downgrade_color(color, ColorDepth::Ansi256),
+            Color::AnsiValue(166)
+        );
+        assert_eq!(downgrade_color(color, ColorDepth::Ansi16), Color::Red);
+    }
+
+    #[test]
+    fn named_and_indexed_colors_are_not_downgraded() {
+        for depth in [ColorDepth::Ansi256, ColorDepth::Ansi16] {
+            assert_eq!(downgrade_color(Color::DarkYellow, depth), Color::DarkYellow);
+            assert_eq!(
+                downgrade_color(Color::AnsiValue(208), depth),
+                Color::AnsiValue(208)
+            );
+        }
+    }
+
+    #[test]
+    fn icon_colors_follow_the_depth_and_the_switch() {
+        let theme = Theme::default_preset().downgrade(ColorDepth::Ansi16);
+        assert_eq!(theme.icon_color(Some(rgb(0xff0000))), Some(Color::Red));
+        assert_eq!(theme.icon_color(None), None);
+
+        let theme = Theme {
+            icon_colors: false,
+            ..theme
+        };
+        assert_eq!(theme.icon_color(Some(rgb(0xff0000))), None);
+    }
+}
@@ -0,0 +1,125 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use crate::dir_util::filter_hidden;
+
+// Directories with more children than this stay collapsed until they are expanded by hand.
+pub const EXPAND_LIMIT: usize = 50;
+
+// One visible directory of the tree, in drawing order.
+#[derive(Debug, Clone)]
+pub struct TreeRow {
+    pub path: PathBuf,
+    // For every ancestor level, whether that ancestor was the last of its siblings.
+    pub guides: Vec<bool>,
+    pub is_last: bool,
+    pub expanded: bool,
+    // Number of subdirectories, None until they were read.
+    pub children: Option<usize>,
+}
+
+impl TreeRow {
+    // 1 for the current directory's children.
+    pub fn level(&self) -> usize {
+        self.guides.len() + 1
+    }
+}
+
+// Several levels below the current directory. Subdirectories are read lazily by the
+// caller, only for directories that are expanded, see `unloaded`.
+#[derive(Debug, Clone, Default)]
+pub struct Tree {
+    // Levels expanded by default, 1 shows only the current directory's children.
+    pub depth: usize,
+    children: HashMap<PathBuf, Vec<PathBuf>>,
+    // Directories expanded or collapsed by hand, the opposite of their default.
+    toggled: HashSet<PathBuf>,
+}
+
+impl Tree {
+    pub fn new(depth: usize) -> Self {
+        Tree {
+            depth,
+            ..Tree::default()
+        }
+    }
+
+    // Forgets all read directories and toggles, e.g. after the current directory changed.
+    pub fn clear(&mut self) {
+        self.children.clear();
+        self.toggled.clear();
+    }
+
+    pub fn set_children(&mut self, dir: PathBuf, dirs: Vec<PathBuf>) {
+        self.children.insert(dir, dirs);
+    }
+
+    pub fn toggle(&mut self, dir: &Path) {
+        if !self.toggled.remove(dir) {
+            self.toggled.insert(dir.to_path_buf());
+        }
+    }
+
+    // Expanded directories whose children have not been read yet.
+    pub fn unloaded(&self, roots: &[PathBuf], show_hidden: bool) -> Vec<PathBuf> {
+        self.rows(roots, show_hidden)
+            .into_iter()
+            .filter(|row| row.children.is_none() && self.is_expanded(&row.path, row.level()))
+            .map(|row| row.path)
+            .collect()
+    }
+
+    // Roots are the current directory's children, already filtered by the caller.
+    pub fn rows(&self, roots: &[PathBuf], show_hidden: bool) -> Vec<TreeRow> {
+        let mut rows = Vec::new();
+        self.walk(roots, 1, &mut Vec::new(), show_hidden, &mut rows);
+        rows
+    }
+
+    fn walk(
+        &self,
+        dirs: &[PathBuf],
+        level: usize,
+        guides: &mut Vec<bool>,
+        show_hidden: bool,
+        rows: &mut Vec<TreeRow>,
+    ) {
+        let dirs = if show_hidden || level == 1 {
+            dirs.to_vec()
+        } else {
+            filter_hidden(dirs)
+        };
+
+        for (i, dir) in dirs.iter().enumerate() {
+            let is_last = i + 1 == dirs.len();
+            let children = self.children.get(dir);
+            let expanded = children.is_some() && self.is_expanded(dir, level);
+
+            rows.push(TreeRow {
+                path: dir.clone(),
+                guides: guides.clone(),
+                is_last,
+                expanded,
+                children: children.map(|c| c.len()),
+            });
+
+            if let Some(children) = children.filter(|_| expanded) {
+                guides.push(is_last);
+                self.walk(children, level + 1, guides, show_hidden, rows);
+                guides.pop();
+            }
+        }
+    }
+
+    fn is_expanded(&self, dir: &Path, level: usize) -> bool {
+        let by_default = level < self.depth
+            && self
+                .children
+                .get(dir)
+                .is_none_or(|children| children.len() <= EXPAND_LIMIT);
+
+        by_default != self.toggled.contains(dir)
+    }
+}
@@ -0,0 +1,300 @@
+use std::{
+    collections::HashMap,
+    fs::{self, Metadata},
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender},
+    },
+    thread,
+};
+
+// Entries counted between two progress updates of a scan.
+const PROGRESS_INTERVAL: u64 = 1000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UsageUpdate {
+    // Entries and bytes counted so far.
+    Progress { entries: u64, bytes: u64 },
+    // Recursive size of a child of the scanned directory.
+    Size(PathBuf, u64),
+    Done,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScanState {
+    Running,
+    Done,
+    // Sizes of the children that were not finished are missing.
+    Cancelled,
+}
+
+// What a scan found out about the children of `dir` so far.
+#[derive(Debug, Clone)]
+pub struct DiskUsage {
+    pub dir: PathBuf,
+    pub sizes: HashMap<PathBuf, u64>,
+    pub entries: u64,
+    pub bytes: u64,
+    pub state: ScanState,
+}
+
+impl DiskUsage {
+    pub fn new(dir: PathBuf) -> Self {
+        DiskUsage {
+            dir,
+            sizes: HashMap::new(),
+            entries: 0,
+            bytes: 0,
+            state: ScanState::Running,
+        }
+    }
+
+    pub fn apply(&mut self, update: UsageUpdate) {
+        if self.state != ScanState::Running {
+            return;
+        }
+
+        match update {
+            UsageUpdate::Progress { entries, bytes } => {
+                self.entries = entries;
+                self.bytes = bytes;
+            }
+            UsageUpdate::Size(path, size) => {
+                self.sizes.insert(path, size);
+            }
+            UsageUpdate::Done => self.state = ScanState::Done,
+        }
+    }
+
+    // `paths` with their sizes, the largest first. Paths not scanned yet come last.
+    pub fn sorted(&self, paths: &[PathBuf]) -> Vec<(PathBuf, Option<u64>)> {
+        let mut rows: Vec<(PathBuf, Option<u64>)> = paths
+            .iter()
+            .map(|path| (path.clone(), self.sizes.get(path).copied()))
+            .collect();
+        rows.sort_by_key(|(_, size)| std::cmp::Reverse(*size));
+        rows
+    }
+}
+
+// Sums up the children of a directory in a background thread. Dropping the scan
+// cancels it.
+pub struct UsageScan {
+    pub dir: PathBuf,
+    updates: Receiver<UsageUpdate>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl UsageScan {
+    pub fn start(dir: PathBuf) -> Self {
+        let (sender, updates) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+
+        let root = dir.clone();
+        let cancelled = Arc::clone(&cancel);
+        thread::spawn(move || scan(&root, &sender, &cancelled));
+
+        UsageScan {
+            dir,
+            updates,
+            cancel,
+        }
+    }
+
+    // Updates sent since the last call, without waiting for new ones.
+    pub fn updates(&self) -> Vec<UsageUpdate> {
+        self.updates.try_iter().collect()
+    }
+}
+
+impl Drop for UsageScan {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+struct Progress<'a> {
+    entries: u64,
+    bytes: u64,
+    sender: &'a Sender<UsageUpdate>,
+    cancel: &'a AtomicBool,
+    // File system of the scanned directory, mount points below it are not entered.
+    device: Option<u64>,
+}
+
+// Sends are allowed to fail, the receiver is gone once the scan was dropped.
+fn scan(dir: &Path, sender: &Sender<UsageUpdate>, cancel: &AtomicBool) {
+    let mut children: Vec<PathBuf> = match fs::read_dir(dir) {
+        Ok(entries) => entries.flatten().map(|entry| entry.path()).collect(),
+        Err(_) => Vec::new(),
+    };
+    children.sort();
+
+    let mut progress = Progress {
+        entries: 0,
+        bytes: 0,
+        sender,
+        cancel,
+        device: fs::metadata(dir).ok().and_then(|m| device(&m)),
+    };
+
+    for child in children {
+        let size = size_of(&child, &mut progress);
+        if cancel.load(Ordering::Relaxed) {
+            return;
+        }
+
+        let _ = sender.send(UsageUpdate::Size(child, size));
+        let _ = sender.send(UsageUpdate::Progress {
+            entries: progress.entries,
+            bytes: progress.bytes,
+        });
+    }
+
+    let _ = sender.send(UsageUpdate::Done);
+}
+
+// Apparent size of `path` and everything below it, like `du -sx --apparent-size`.
+// Symlinks are not followed and unreadable entries count as empty.
+fn size_of(path: &Path, progress: &mut Progress) -> u64 {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(m) => m,
+        Err(_) => return 0,
+    };
+
+    let mut size = metadata.len();
+    progress.entries += 1;
+    progress.bytes += size;
+
+    if progress.entries.is_multiple_of(PROGRESS_INTERVAL) {
+        let _ = progress.sender.send(UsageUpdate::Progress {
+            entries: progress.entries,
+            bytes: progress.bytes,
+        });
+    }
+
+    if metadata.is_dir()
+        && device(&metadata) == progress.device
+        && let Ok(entries) = fs::read_dir(path)
+    {
+        for entry in entries.flatten() {
+            if progress.cancel.load(Ordering::Relaxed) {
+                break;
+            }
+            size += size_of(&entry.path(), progress);
+        }
+    }
+
+    size
+}
+
+#[cfg(unix)]
+fn device(meta: &Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+
+    Some(meta.dev())
+}
+
+#[cfg(not(unix))]
+fn device(_meta: &Metadata) -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp_dir::TempDir;
+    use std::time::{Duration, Instant};
+
+    // a/one (100), a/sub/two (50), b/ and c (7).
+    fn tree(temp: &TempDir) -> PathBuf {
+        let dir = temp.path();
+        fs::create_dir_all(dir.join("a/sub")).unwrap();
+        fs::create_dir(dir.join("b")).unwrap();
+        fs::write(dir.join("a/one"), [0; 100]).unwrap();
+        fs::write(dir.join("a/sub/two"), [0; 50]).unwrap();
+        fs::write(dir.join("c"), [0; 7]).unwrap();
+        dir.to_path_buf()
+    }
+
+    fn len(path: PathBuf) -> u64 {
+        fs::symlink_metadata(path).unwrap().len()
+    }
+
+    fn wait_for_done(scan: &UsageScan) -> Vec<UsageUpdate> {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let mut updates = Vec::new();
+
+        while updates.last() != Some(&UsageUpdate::Done) {
+            assert!(Instant::now() < deadline, "the scan did not finish");
+            updates.extend(scan.updates());
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        updates
+    }
+
+    #[test]
+    fn scan_sums_up_the_children() {
+        let temp = TempDir::new("usage");
+        let dir = tree(&temp);
+
+        let a = len(dir.join("a")) + len(dir.join("a/sub")) + 100 + 50;
+        let b = len(dir.join("b"));
+
+        let mut usage = DiskUsage::new(dir.clone());
+        for update in wait_for_done(&UsageScan::start(dir.clone())) {
+            usage.apply(update);
+        }
+
+        assert_eq!(usage.state, ScanState::Done);
+        assert_eq!(usage.entries, 6);
+        assert_eq!(usage.bytes, a + b + 7);
+        assert_eq!(
+            usage.sizes,
+            HashMap::from([(dir.join("a"), a), (dir.join("b"), b), (dir.join("c"), 7)])
+        );
+    }
+
+    #[test]
+    fn cancelled_scans_send_nothing_more() {
+        let temp = TempDir::new("usage-cancel");
+        let dir = tree(&temp);
+
+        let (sender, updates) = mpsc::channel();
+        scan(&dir, &sender, &AtomicBool::new(true));
+        drop(sender);
+        assert_eq!(updates.iter().collect::<Vec<_>>(), []);
+
+        let scan = UsageScan::start(dir);
+        let cancel = Arc::clone(&scan.cancel);
+        drop(scan);
+        assert!(cancel.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn sorted_puts_the_largest_first_and_unknown_last() {
+        let paths = ["a", "b", "c", "d"].map(PathBuf::from);
+        let mut usage = DiskUsage::new(PathBuf::from("/"));
+        usage.apply(UsageUpdate::Size(PathBuf::from("c"), 30));
+        usage.apply(UsageUpdate::Size(PathBuf::from("a"), 10));
+        usage.apply(UsageUpdate::Size(PathBuf::from("d"), 0));
+
+        assert_eq!(
+            usage.sorted(&paths),
+            [
+                (PathBuf::from("c"), Some(30)),
+                (PathBuf::from("a"), Some(10)),
+                (PathBuf::from("d"), Some(0)),
+                (PathBuf::from("b"), None),
+            ]
+        );
+
+        // Stopped scans keep what they had.
+        usage.state = ScanState::Cancelled;
+        usage.apply(UsageUpdate::Size(PathBuf::from("b"), 99));
+        assert_eq!(usage.sizes.get(Path::new("b")), None);
+    }
+}
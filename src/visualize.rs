@@ -1,30 +1,110 @@
-use crate::dir_util::build_char_map;
-use crate::dir_util::get_name;
-use crate::history::PathHistory;
-use crate::icons::icon_for_file;
-use crate::mode::Mode;
 use crate::screen::Screen;
+use crate::theme::Theme;
+use twiggle::dir_util::build_char_map;
+use twiggle::dir_util::escape_os_str;
+use twiggle::dir_util::filter_hidden;
+use twiggle::dir_util::get_name;
+use twiggle::dir_util::is_broken_symlink;
+use twiggle::dir_util::is_directory;
+use twiggle::dir_util::is_readable;
+use twiggle::dir_util::list_dir;
+use twiggle::dir_util::physical_path;
+use twiggle::dir_util::starts_with;
+use twiggle::dir_util::symlink_target;
+use twiggle::hints::hint_labels;
+use twiggle::icons::{IconProvider, style_for_file};
+use twiggle::ls_colors::LsColors;
+use twiggle::metadata::{COLUMNS, EntryInfo, fit_columns, format_columns, human_size};
+use twiggle::mode::Mode;
+use twiggle::navigator::Navigator;
+use twiggle::project::ProjectCache;
+use twiggle::tree::TreeRow;
+use twiggle::usage::ScanState;
 
-use crossterm::style::Color;
-use crossterm::style::Stylize;
-use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
+use crossterm::style::{Attribute, Color, ContentStyle, Stylize};
+use crossterm::terminal::{disable_raw_mode, enable_raw_mode, size};
 
+use std::collections::HashMap;
 use std::path::Path;
 use std::path::PathBuf;
+use std::time::SystemTime;
+
+// Terminals narrower than this show the single column instead of the column view.
+const COLUMN_VIEW_MIN_WIDTH: usize = 60;
+
+// Text of a pane in the column view, styled after it was cut to the pane width.
+type Segment = (String, ContentStyle);
+
+// Lines of Select mode besides the directories: headers, page info, files and help.
+const SELECT_RESERVED_ROWS: usize = 11;
+
+// Room kept for the key, the icon and the start of the name in the long view.
+const LONG_VIEW_RESERVED: usize = 30;
+
+// Width of the bars in Usage mode, the largest entry fills it.
+const USAGE_BAR_WIDTH: usize = 20;
+
+// Metadata columns of the entries on screen, aligned over all of them.
+struct LongView {
+    infos: HashMap<PathBuf, EntryInfo>,
+    widths: [Option<usize>; COLUMNS],
+}
+
+impl LongView {
+    fn new(paths: &[PathBuf]) -> Self {
+        let now = SystemTime::now();
+        let infos: HashMap<PathBuf, EntryInfo> = paths
+            .iter()
+            .map(|path| (path.clone(), EntryInfo::read(path, now)))
+            .collect();
+
+        let width = size().map(|(columns, _)| columns as usize).unwrap_or(80);
+        let rows = infos.values().cloned().collect::<Vec<EntryInfo>>();
+
+        LongView {
+            widths: fit_columns(&rows, LONG_VIEW_RESERVED, width),
+            infos,
+        }
+    }
+
+    fn columns(&self, path: &Path) -> String {
+        self.infos
+            .get(path)
+            .map(|info| format_columns(info, &self.widths))
+            .unwrap_or_default()
+    }
+
+    // Spaces as wide as the columns less `taken`, for rows without metadata of their own.
+    fn padding(&self, taken: usize) -> String {
+        let width: usize = self.widths.iter().flatten().map(|width| width + 2).sum();
+        " ".repeat(width.saturating_sub(taken))
+    }
+}
 
 pub struct View {
     screen: Screen,
-    keybinds: String,
-    current_mode: Mode,
     debug_messages: Vec<String>,
-    use_colors: bool,
-    use_icons: bool,
+    error_message: Option<String>,
+    theme: Theme,
+    ls_colors: Option<LsColors>,
+    icons: Option<IconProvider>,
     use_debug: bool,
     is_dirty: bool,
+    // Nothing is drawn, used when replaying keys without a terminal.
+    headless: bool,
+    // Shows the path with symlinks resolved, like `cd -P`.
+    physical: bool,
+    // Detected projects for the badges after directory names, None if they are disabled.
+    projects: Option<ProjectCache>,
+    // Lists archives with the directories in the column panes, like the navigator.
+    browse_archives: bool,
 }
 
 impl Drop for View {
     fn drop(&mut self) {
+        if self.headless {
+            return;
+        }
         let _ = disable_raw_mode();
         let _ = self.screen.show_cursor();
     }
@@ -33,25 +113,39 @@ impl Drop for View {
 impl View {
     pub fn new(
         screen: Screen,
-        keybinds: String,
-        use_colors: bool,
-        use_icons: bool,
+        theme: Theme,
+        ls_colors: Option<LsColors>,
+        icons: Option<IconProvider>,
         use_debug: bool,
+        headless: bool,
+        physical: bool,
     ) -> Self {
         View {
             screen,
-            keybinds,
-            current_mode: Mode::Normal,
             debug_messages: Vec::new(),
-            use_colors,
-            use_icons,
+            error_message: None,
+            theme,
+            ls_colors,
+            icons,
             use_debug,
             is_dirty: true,
+            headless,
+            physical,
+            projects: Some(ProjectCache::default()),
+            browse_archives: true,
         }
     }
 
-    pub fn change_mode(&mut self, mode: Mode) {
-        self.current_mode = mode;
+    pub fn is_headless(&self) -> bool {
+        self.headless
+    }
+
+    pub fn hide_project_badges(&mut self) {
+        self.projects = None;
+    }
+
+    pub fn hide_archives(&mut self) {
+        self.browse_archives = false;
     }
 
     pub fn debug_message(&mut self, message: String) {
@@ -61,12 +155,21 @@ impl View {
         }
     }
 
+    pub fn error_message(&mut self, message: String) {
+        if self.headless {
+            eprintln!("{}", message);
+            return;
+        }
+        self.dirty();
+        self.error_message = Some(message);
+    }
+
     pub fn dirty(&mut self) {
         self.is_dirty = true;
     }
 
     pub fn prepare_screen(&mut self) -> std::io::Result<()> {
-        if self.is_dirty {
+        if self.is_dirty && !self.headless {
             disable_raw_mode()?;
             self.screen.hide_cursor()?;
             self.screen.move_up()?;
@@ -77,109 +180,198 @@ impl View {
     pub fn clear_rest(&mut self) -> std::io::Result<()> {
         if self.is_dirty {
             self.debug_messages.clear();
-            self.screen.clear_rest()?;
-            self.screen.show_cursor()?;
+            self.error_message = None;
             self.is_dirty = false;
-            enable_raw_mode()?;
+
+            if !self.headless {
+                self.screen.clear_rest()?;
+                self.screen.show_cursor()?;
+                enable_raw_mode()?;
+            }
         }
         Ok(())
     }
 
     pub fn clear_screen(&mut self) -> std::io::Result<()> {
+        if self.headless {
+            return Ok(());
+        }
         disable_raw_mode()?;
         self.screen.move_up()?;
         self.screen.clear_rest()?;
         Ok(())
     }
 
-    #[allow(clippy::too_many_arguments)] // I know it's bad
-    pub fn display(
-        &mut self,
-        current_dir: &Path,
-        dirs: &[PathBuf],
-        files: &[PathBuf],
-        prefix: &str,
-        current_page: usize,
-        history: &PathHistory,
-        cursor_index: &Option<usize>,
-    ) -> std::io::Result<()> {
-        if self.is_dirty {
-            self.print_screen(
-                current_dir,
-                dirs,
-                files,
-                prefix,
-                current_page,
-                history,
-                cursor_index,
-            )?;
+    // Hands the terminal over to a child process.
+    pub fn suspend(&mut self) -> std::io::Result<()> {
+        if self.headless {
+            return Ok(());
         }
+        self.clear_screen()?;
+        self.screen.show_cursor()?;
         Ok(())
     }
 
-    #[allow(clippy::too_many_arguments)] // I know it's bad
-    fn print_screen(
-        &mut self,
-        current_dir: &Path,
-        dirs: &[PathBuf],
-        files: &[PathBuf],
-        prefix: &str,
-        current_page: usize,
-        history: &PathHistory,
-        cursor_index: &Option<usize>,
-    ) -> std::io::Result<()> {
+    // Starts a fresh frame below whatever the child process left behind.
+    pub fn resume(&mut self) {
+        self.screen.reset();
+        // The command may have created or removed projects.
+        if let Some(projects) = &mut self.projects {
+            projects.clear();
+        }
+        self.dirty();
+    }
+
+    // How many directories fit on a Select page, None without a terminal.
+    pub fn select_rows(&self) -> Option<usize> {
+        if self.headless {
+            return None;
+        }
+
+        size()
+            .ok()
+            .map(|(_, rows)| (rows as usize).saturating_sub(SELECT_RESERVED_ROWS))
+    }
+
+    pub fn display(&mut self, navigator: &Navigator) -> std::io::Result<()> {
+        if self.is_dirty && !self.headless {
+            self.print_screen(navigator)?;
+        }
+        Ok(())
+    }
+
+    fn print_screen(&mut self, navigator: &Navigator) -> std::io::Result<()> {
+        let history = &navigator.history;
+        let dirs = navigator.dirs();
+        let files = navigator.files();
+
         if !self.debug_messages.is_empty() {
-            self.screen.write(" Debug ".black().on_cyan().bold())?;
+            self.screen.write(
+                " Debug "
+                    .with(self.theme.header_text)
+                    .on(self.theme.debug)
+                    .bold(),
+            )?;
 
             for message in &self.debug_messages {
-                self.screen.write(message.clone().cyan())?;
+                self.screen.write(message.clone().with(self.theme.debug))?;
             }
 
             self.screen.empty_line()?;
         }
 
-        let path_str = display_path(current_dir);
+        let path_str = if self.physical {
+            display_path(&physical_path(&navigator.current_dir))
+        } else {
+            display_path(&navigator.current_dir)
+        };
 
-        let blue = self.color_or_white(Color::Blue);
-        let header = format!(" {} ", path_str).black().on(blue).bold();
+        let header = format!(" {} ", path_str)
+            .with(self.theme.header_text)
+            .on(self.theme.path)
+            .bold();
 
-        let mut history_str = format!("[{}/{}]", history.index + 1, history.buffer.len()).blue();
+        let mut history_str =
+            format!("[{}/{}]", history.index + 1, history.buffer.len()).with(self.theme.history);
 
         if history.index + 1 == history.buffer.len() {
-            history_str = String::from("").blue();
+            history_str = String::from("").with(self.theme.history);
         }
 
         self.screen.write(format!("{} {}", header, history_str))?;
+
+        if let Some(message) = &self.error_message {
+            self.screen.write(message.clone().with(self.theme.error))?;
+        }
+
+        if matches!(navigator.mode, Mode::Filter) || !navigator.filter.is_empty() {
+            self.print_filter(navigator)?;
+        }
+
+        if let Mode::Prompt = navigator.mode {
+            self.print_prompt(navigator)?;
+        }
+
         self.screen.empty_line()?;
 
-        match self.current_mode {
-            Mode::Normal => self.print_normal(dirs, cursor_index),
-            Mode::Select => {
-                self.print_select(dirs, prefix, current_page, cursor_index.unwrap_or(0))
+        let long_view = if navigator.long_view {
+            let mut paths = visible_dirs(navigator);
+            paths.extend(files.iter().cloned());
+            Some(LongView::new(&paths))
+        } else {
+            None
+        };
+
+        let (width, height) = size()
+            .map(|(w, h)| (w as usize, h as usize))
+            .unwrap_or((80, 24));
+        let column_view = navigator.column_view && width >= COLUMN_VIEW_MIN_WIDTH;
+
+        match navigator.mode {
+            Mode::Normal | Mode::Filter | Mode::Prompt if column_view => {
+                self.print_columns(navigator, width, height)
             }
+            Mode::Normal | Mode::Filter | Mode::Prompt if navigator.hints => self.print_hints(
+                dirs,
+                &navigator.hint_labels(),
+                &navigator.prefix,
+                &navigator.cursor_index,
+                long_view.as_ref(),
+            ),
+            Mode::Normal | Mode::Filter | Mode::Prompt => {
+                self.print_normal(dirs, &navigator.cursor_index, long_view.as_ref())
+            }
+            Mode::Select => self.print_select(navigator, long_view.as_ref()),
+            Mode::Tree => self.print_tree(
+                &navigator.tree_rows(),
+                &navigator.tree_labels(),
+                &navigator.prefix,
+                &navigator.cursor_index,
+                long_view.as_ref(),
+            ),
+            Mode::Projects => self.print_projects(navigator),
+            Mode::Usage => self.print_usage(navigator),
         }?;
 
+        // Usage mode lists the files with their sizes.
+        if let Mode::Usage = navigator.mode {
+            self.screen
+                .write("<Esc> to leave | <Enter> to change directory")?;
+            return Ok(());
+        }
+
         // let file_str = files
         //     .iter()
         //     .map(|f| f.file_name().unwrap().to_string_lossy().to_string())
         //     .collect::<Vec<String>>()
         //     .join(" ");
 
+        let magenta = self.theme.file;
+        let red = self.theme.broken_link;
+
         let file_icons = files
             .iter()
             .map(|file| {
-                let icon = if self.use_icons {
-                    &format!("{} ", icon_for_file(file))[..]
+                let name = format!("{}{}", get_name(file), link_suffix(file));
+                let fallback = if is_broken_symlink(file) {
+                    red
                 } else {
-                    ""
+                    magenta
                 };
-                let name = get_name(file);
-                format!("{}{}", icon, name)
-            })
-            .collect::<Vec<String>>()
-            .join("  ");
+                let style = self.entry_style(file, fallback);
+                let columns = long_view
+                    .as_ref()
+                    .map(|long_view| format!("    {}", long_view.columns(file)))
+                    .unwrap_or_default();
 
-        let magenta = self.color_or_white(Color::Magenta);
+                format!(
+                    "{}{}{}",
+                    columns,
+                    self.icon_prefix(file, style, " "),
+                    style.apply(name)
+                )
+            })
+            .collect::<Vec<String>>();
 
         let files_header_str = if files.is_empty() {
             " No Files "
@@ -187,10 +379,20 @@ impl View {
             " Files "
         };
 
-        let files_header = files_header_str.black().on(magenta).bold();
-        let file_info = format!("{} {}", files_header, file_icons.with(magenta));
-
-        self.screen.write(file_info)?;
+        let files_header = files_header_str
+            .with(self.theme.header_text)
+            .on(magenta)
+            .bold();
+        // The long view lists files one per row, below their header.
+        if long_view.is_some() {
+            self.screen.write(files_header)?;
+            for file in file_icons {
+                self.screen.write(file)?;
+            }
+        } else {
+            self.screen
+                .write(format!("{} {}", files_header, file_icons.join("  ")))?;
+        }
         self.screen.empty_line()?;
         self.screen
             .write("<Esc> to cancel | <Enter> to change directory")?;
@@ -202,18 +404,26 @@ impl View {
         &mut self,
         dirs: &[PathBuf],
         cursor_index: &Option<usize>,
+        long_view: Option<&LongView>,
     ) -> std::io::Result<()> {
-        //let dir_single_icon = if self.use_icons { "  " } else { "" };
-        let dir_multiple_icon = if self.use_icons { "󰉓  " } else { "" };
+        let dir_multiple_icon = match &self.icons {
+            Some(icons) => format!("{}  ", icons.group_icon()),
+            None => String::new(),
+        };
 
-        let yellow = self.color_or_white(Color::DarkYellow);
+        let yellow = self.theme.directory;
+        let white = self.theme.directory_group;
         let dirs_header_str = if dirs.is_empty() {
             " No Directories "
         } else {
             " Directories "
         };
-        self.screen
-            .write(dirs_header_str.black().on(yellow).bold())?;
+        self.screen.write(
+            dirs_header_str
+                .with(self.theme.header_text)
+                .on(yellow)
+                .bold(),
+        )?;
 
         let char_map = build_char_map(dirs);
 
@@ -221,38 +431,63 @@ impl View {
             let (char, directories) = directories_with_char;
             let is_multiple = directories.len() > 1;
 
-            let dir_str = directories
-                .iter()
-                .map(|d| d.file_name().unwrap().to_string_lossy().to_string())
-                .collect::<Vec<String>>()
-                .join(" ");
-
-            let mut char_disp = char.white();
+            let mut char_disp = char.with(self.theme.key);
 
             if let Some(i) = cursor_index
                 && i == &index
             {
-                char_disp = char_disp.on_white().black();
+                char_disp = char_disp.on(self.theme.key).with(self.theme.header_text);
             }
 
             if is_multiple {
+                // Unreadable directories are dimmed one by one.
+                let dir_str = directories
+                    .iter()
+                    .map(|d| {
+                        let name = self.entry_style(d, white).apply(format!(
+                            "{}{}",
+                            get_name(d),
+                            link_suffix(d)
+                        ));
+                        if is_readable(d) {
+                            name.to_string()
+                        } else {
+                            name.dim().to_string()
+                        }
+                    })
+                    .collect::<Vec<String>>()
+                    .join(" ");
+
+                // The `?` takes one column of the padding, so the names line up.
                 self.screen.write(format!(
-                    "[{}?] {}",
+                    "[{}?] {}{}{}",
                     char_disp,
-                    format!("{}{}", dir_multiple_icon, dir_str).white()
+                    long_view
+                        .map(|long_view| long_view.padding(1))
+                        .unwrap_or_default(),
+                    dir_multiple_icon.as_str().with(white),
+                    dir_str
                 ))?;
             } else {
                 let directory = &directories[0];
-                let icon: &str = if self.use_icons {
-                    &format!("{}  ", icon_for_file(directory))[..]
-                } else {
-                    ""
-                };
+                let mut style = self.entry_style(directory, yellow);
+                if !is_readable(directory) {
+                    style.attributes.set(Attribute::Dim);
+                }
 
+                let dir_disp =
+                    style.apply(format!("{}{}", get_name(directory), link_suffix(directory)));
+
+                let badges = self.project_badges(directory);
                 self.screen.write(format!(
-                    "[{}] {}",
+                    "[{}] {}{}{}{}",
                     char_disp,
-                    format!("{}{}", icon, dir_str).with(yellow)
+                    long_view
+                        .map(|long_view| long_view.columns(directory))
+                        .unwrap_or_default(),
+                    self.icon_prefix(directory, style, "  "),
+                    dir_disp,
+                    badges
                 ))?;
             }
         }
@@ -262,72 +497,566 @@ impl View {
         Ok(())
     }
 
-    fn print_select(
+    // Parent, current and highlighted directory as three panes. Only the current pane
+    // has jump keys or hint labels, the other two are cut off once they are longer
+    // than the screen.
+    fn print_columns(
         &mut self,
-        dirs: &[PathBuf],
-        prefix: &str,
-        current_page: usize,
-        cursor_index: usize,
+        navigator: &Navigator,
+        width: usize,
+        height: usize,
     ) -> std::io::Result<()> {
-        //let dir_single_icon = if self.use_icons { "  " } else { "" };
+        let yellow = self.theme.directory;
+        let magenta = self.theme.file;
+
+        let dirs_header_str = if navigator.dirs().is_empty() {
+            " No Directories "
+        } else {
+            " Directories "
+        };
+        self.screen.write(
+            dirs_header_str
+                .with(self.theme.header_text)
+                .on(yellow)
+                .bold(),
+        )?;
+
+        let parent = match navigator.current_dir.parent() {
+            Some(parent) => self
+                .read_pane(parent, navigator.show_hidden, false)
+                .into_iter()
+                .map(|path| {
+                    let mut style = self.entry_style(&path, yellow);
+                    if path == navigator.current_dir {
+                        style.attributes.set(Attribute::Reverse);
+                    }
+                    self.pane_entry(&path, style)
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+
+        let current = if navigator.hints {
+            self.hint_pane(navigator)
+        } else {
+            self.key_pane(navigator)
+        };
+
+        let child = match navigator.highlighted_dir() {
+            Some(dir) => self
+                .read_pane(&dir, navigator.show_hidden, true)
+                .into_iter()
+                .map(|path| {
+                    let fallback = if is_directory(&path) { yellow } else { magenta };
+                    self.pane_entry(&path, self.entry_style(&path, fallback))
+                })
+                .collect(),
+            None => vec![vec![(
+                String::from("<Tab> to preview"),
+                ContentStyle::new().dim(),
+            )]],
+        };
+
+        // Keeps the header and the files line on screen, the current pane is never cut.
+        let limit = height.saturating_sub(8).max(current.len()).max(1);
+        let rows = current.len().max(parent.len()).max(child.len()).min(limit);
+
+        let separator = ContentStyle::new().dim().apply(" │ ");
+        // One column stays free, a line filling the whole width would wrap.
+        let available = width - 2 * 3 - 1;
+        let parent_width = available / 4;
+        let current_width = available * 3 / 8;
+        let child_width = available - parent_width - current_width;
+
+        for i in 0..rows {
+            self.screen.write(format!(
+                "{}{}{}{}{}",
+                fit_pane(&pane_row(&parent, i, rows), parent_width),
+                separator,
+                fit_pane(&pane_row(&current, i, rows), current_width),
+                separator,
+                fit_pane(&pane_row(&child, i, rows), child_width),
+            ))?;
+        }
+
+        self.screen.empty_line()?;
+
+        Ok(())
+    }
+
+    // The current pane with hint labels, one row per directory.
+    fn hint_pane(&self, navigator: &Navigator) -> Vec<Vec<Segment>> {
+        let yellow = self.theme.directory;
+        let labels = navigator.hint_labels();
+        let label_width = labels.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+
+        let mut current = Vec::new();
+
+        for (index, (directory, label)) in navigator.dirs().iter().zip(&labels).enumerate() {
+            let mut key_style = ContentStyle::new().with(self.theme.key);
+            if !label.starts_with(&navigator.prefix) {
+                key_style = key_style.dim();
+            }
+            if navigator.cursor_index == Some(index) {
+                key_style = key_style.on(self.theme.key).with(self.theme.header_text);
+            }
+
+            let mut style = self.entry_style(directory, yellow);
+            if !is_readable(directory) {
+                style.attributes.set(Attribute::Dim);
+            }
+
+            let padding = " ".repeat(label_width - label.chars().count());
+            let mut segments = vec![
+                (String::from("["), ContentStyle::new()),
+                (label.clone(), key_style),
+                (format!("]{} ", padding), ContentStyle::new()),
+            ];
+            segments.extend(self.pane_entry(directory, style));
+            current.push(segments);
+        }
+
+        current
+    }
+
+    // The current pane with jump keys, directories sharing a letter share a row.
+    fn key_pane(&self, navigator: &Navigator) -> Vec<Vec<Segment>> {
+        let yellow = self.theme.directory;
+        let white = self.theme.directory_group;
+
+        let mut current = Vec::new();
+        for (index, (c, directories)) in build_char_map(navigator.dirs()).iter().enumerate() {
+            let mut key_style = ContentStyle::new().with(self.theme.key);
+            if navigator.cursor_index == Some(index) {
+                key_style = key_style.on(self.theme.key).with(self.theme.header_text);
+            }
+
+            let mut segments = vec![
+                (String::from("["), ContentStyle::new()),
+                (c.to_string(), key_style),
+            ];
+
+            if directories.len() > 1 {
+                let names = directories
+                    .iter()
+                    .map(|d| get_name(d))
+                    .collect::<Vec<String>>()
+                    .join(" ");
+                segments.push((String::from("?] "), ContentStyle::new()));
+                segments.push((names, ContentStyle::new().with(white)));
+            } else {
+                let mut style = self.entry_style(&directories[0], yellow);
+                if !is_readable(&directories[0]) {
+                    style.attributes.set(Attribute::Dim);
+                }
+                segments.push((String::from("] "), ContentStyle::new()));
+                segments.extend(self.pane_entry(&directories[0], style));
+            }
+
+            current.push(segments);
+        }
+
+        current
+    }
+
+    // Directories of `dir`, followed by its files if `with_files` is set.
+    fn read_pane(&self, dir: &Path, show_hidden: bool, with_files: bool) -> Vec<PathBuf> {
+        let (dirs, files) = list_dir(dir, self.browse_archives).unwrap_or_default();
+        let mut paths = dirs;
+        if with_files {
+            paths.extend(files);
+        }
+
+        if show_hidden {
+            paths
+        } else {
+            filter_hidden(&paths)
+        }
+    }
+
+    fn pane_entry(&self, path: &Path, style: ContentStyle) -> Vec<Segment> {
+        let mut segments = Vec::new();
 
-        let green = self.color_or_white(Color::DarkGreen);
+        if let Some(icons) = &self.icons {
+            let (glyph, color) = icons.icon(path);
+            let mut icon_style = style;
+            if let Some(color) = self.theme.icon_color(color) {
+                icon_style.foreground_color = Some(color);
+            }
+            segments.push((format!("{} ", glyph), icon_style));
+        }
+
+        segments.push((format!("{}{}", get_name(path), link_suffix(path)), style));
+        segments
+    }
+
+    fn print_tree(
+        &mut self,
+        rows: &[TreeRow],
+        labels: &[String],
+        prefix: &str,
+        cursor_index: &Option<usize>,
+        long_view: Option<&LongView>,
+    ) -> std::io::Result<()> {
+        let yellow = self.theme.directory;
+        let header_str = if rows.is_empty() {
+            " No Directories "
+        } else {
+            " Tree "
+        };
         self.screen
-            .write(format!(" Select [{}] ", &prefix).black().on(green).bold())?;
+            .write(header_str.with(self.theme.header_text).on(yellow).bold())?;
 
-        let filtered_dirs: Vec<PathBuf> = dirs
-            .iter()
-            .filter(|dir| {
-                dir.file_name()
-                    .map(|os_str| os_str.to_string_lossy().starts_with(prefix))
-                    .unwrap_or(false)
+        let label_width = labels.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+
+        for (index, (row, label)) in rows.iter().zip(labels).enumerate() {
+            let mut connectors: String = row
+                .guides
+                .iter()
+                .map(|&last| if last { "    " } else { "│   " })
+                .collect();
+            connectors.push_str(if row.is_last {
+                "└── "
+            } else {
+                "├── "
+            });
+
+            // Collapsed directories show how many subdirectories they hide.
+            let collapsed = match row.children {
+                Some(n) if n > 0 && !row.expanded => format!(" [+{}]", n).dim().to_string(),
+                _ => String::new(),
+            };
+
+            let label = self.hint_label(label, label_width, prefix, cursor_index == &Some(index));
+            let line = self.labeled_row(&row.path, &label, long_view, &connectors);
+            self.screen.write(format!("{}{}", line, collapsed))?;
+        }
+
+        self.screen.empty_line()?;
+
+        Ok(())
+    }
+
+    // The `/` filter, with a block cursor while it is typed into.
+    fn print_filter(&mut self, navigator: &Navigator) -> std::io::Result<()> {
+        let cursor = if matches!(navigator.mode, Mode::Filter) {
+            " ".on(self.theme.key).to_string()
+        } else {
+            String::new()
+        };
+
+        let matches = navigator.dirs().len() + navigator.files().len();
+        let info = format!(
+            "{} {}{}",
+            matches,
+            if matches == 1 { "match" } else { "matches" },
+            if navigator.sticky_filter {
+                ", sticky"
+            } else {
+                ""
+            }
+        );
+
+        self.screen.write(format!(
+            "{}{}  {}",
+            format!("/{}", navigator.filter).with(self.theme.select),
+            cursor,
+            info.dim()
+        ))
+    }
+
+    // The path prompt, followed by the directories it could be completed to. Completed
+    // names can contain control characters, only those are escaped.
+    fn print_prompt(&mut self, navigator: &Navigator) -> std::io::Result<()> {
+        let prompt: String = navigator
+            .prompt
+            .chars()
+            .map(|c| {
+                if c.is_control() {
+                    c.escape_default().to_string()
+                } else {
+                    c.to_string()
+                }
             })
-            .cloned()
             .collect();
 
-        let page_size = 10;
-        let start_idx = current_page * page_size;
-        let end_idx = (start_idx + page_size).min(filtered_dirs.len());
+        self.screen.write(format!(
+            "{}{}",
+            format!(":{}", prompt).with(self.theme.path),
+            " ".on(self.theme.key)
+        ))?;
+
+        if !navigator.prompt_candidates.is_empty() {
+            self.screen
+                .write(navigator.prompt_candidates.join("  ").dim())?;
+        }
+
+        Ok(())
+    }
+
+    // Normal mode with hints, one row and one label per directory.
+    fn print_hints(
+        &mut self,
+        dirs: &[PathBuf],
+        labels: &[String],
+        prefix: &str,
+        cursor_index: &Option<usize>,
+        long_view: Option<&LongView>,
+    ) -> std::io::Result<()> {
+        let dirs_header_str = if dirs.is_empty() {
+            " No Directories "
+        } else {
+            " Directories "
+        };
+        self.screen.write(
+            dirs_header_str
+                .with(self.theme.header_text)
+                .on(self.theme.directory)
+                .bold(),
+        )?;
+
+        let label_width = labels.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+
+        for (index, (directory, label)) in dirs.iter().zip(labels).enumerate() {
+            let label = self.hint_label(label, label_width, prefix, cursor_index == &Some(index));
+            let line = self.labeled_row(directory, &label, long_view, "");
+            self.screen.write(line)?;
+        }
+
+        self.screen.empty_line()?;
+
+        Ok(())
+    }
+
+    // Repositories with their path below the projects directory.
+    fn print_projects(&mut self, navigator: &Navigator) -> std::io::Result<()> {
+        self.screen.write(
+            format!(" Projects in {} ", display_path(&navigator.projects_dir))
+                .with(self.theme.header_text)
+                .on(self.theme.directory)
+                .bold(),
+        )?;
+
+        let labels = navigator.project_labels();
+        let label_width = labels.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+
+        for (index, (project, label)) in navigator.projects.iter().zip(&labels).enumerate() {
+            let label = self.hint_label(
+                label,
+                label_width,
+                &navigator.prefix,
+                navigator.cursor_index == Some(index),
+            );
+            let name = project
+                .strip_prefix(&navigator.projects_dir)
+                .unwrap_or(project);
+            let style = self.entry_style(project, self.theme.directory);
+
+            self.screen.write(format!(
+                "{} {}{}",
+                label,
+                self.icon_prefix(project, style, "  "),
+                style.apply(escape_os_str(name.as_os_str()))
+            ))?;
+        }
+
+        self.screen.empty_line()?;
+
+        Ok(())
+    }
+
+    // Directories and files as a bar chart of their sizes, only directories have labels.
+    fn print_usage(&mut self, navigator: &Navigator) -> std::io::Result<()> {
+        let usage = match &navigator.usage {
+            Some(usage) => usage,
+            None => return Ok(()),
+        };
+
+        self.screen.write(
+            " Disk Usage "
+                .with(self.theme.header_text)
+                .on(self.theme.directory)
+                .bold(),
+        )?;
+
+        let status = match usage.state {
+            ScanState::Running => format!(
+                "Scanning… {} entries, {}  <Esc> to stop",
+                usage.entries,
+                human_size(usage.bytes)
+            ),
+            ScanState::Done => format!("{} in {} entries", human_size(usage.bytes), usage.entries),
+            ScanState::Cancelled => format!(
+                "Stopped after {} entries, sizes are incomplete",
+                usage.entries
+            ),
+        };
+        self.screen.write(status.dim())?;
+
+        // Directories are labeled in the order they are drawn in, like the navigator does.
+        let dirs = navigator.usage_dirs();
+        let labels = hint_labels(dirs.len(), &navigator.hint_alphabet);
+        let label_width = labels.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+
+        let mut paths = dirs.clone();
+        paths.extend(navigator.files().iter().cloned());
+        let rows = usage.sorted(&paths);
+        let largest = rows.iter().filter_map(|(_, size)| *size).max().unwrap_or(0);
+        let highlighted = navigator.highlighted_dir();
+
+        for (path, size) in rows {
+            let label = match dirs.iter().position(|dir| dir == &path) {
+                Some(index) => self.hint_label(
+                    &labels[index],
+                    label_width,
+                    &navigator.prefix,
+                    highlighted.as_ref() == Some(&path),
+                ),
+                None => " ".repeat(label_width + 2),
+            };
+
+            let (size_str, filled) = match size {
+                Some(size) if largest > 0 => (
+                    human_size(size),
+                    (size as u128 * USAGE_BAR_WIDTH as u128).div_ceil(largest as u128) as usize,
+                ),
+                Some(size) => (human_size(size), 0),
+                None => (String::from("…"), 0),
+            };
+
+            let fallback = if is_directory(&path) {
+                self.theme.directory
+            } else {
+                self.theme.file
+            };
+            let style = self.entry_style(&path, fallback);
+
+            self.screen.write(format!(
+                "{} {:>6} {}{} {}{}",
+                label,
+                size_str,
+                "█".repeat(filled).with(self.theme.select),
+                "░".repeat(USAGE_BAR_WIDTH - filled).dim(),
+                self.icon_prefix(&path, style, "  "),
+                style.apply(format!("{}{}", get_name(&path), link_suffix(&path)))
+            ))?;
+        }
+
+        self.screen.empty_line()?;
+
+        Ok(())
+    }
+
+    // Labels that no longer match the typed keys are dimmed.
+    fn hint_label(&self, label: &str, width: usize, prefix: &str, is_cursor: bool) -> String {
+        let mut label_disp = label.with(self.theme.key);
+        if !label.starts_with(prefix) {
+            label_disp = label_disp.dim();
+        }
+        if is_cursor {
+            label_disp = label_disp.on(self.theme.key).with(self.theme.header_text);
+        }
+        let padding = " ".repeat(width.saturating_sub(label.chars().count()));
+
+        format!("[{}]{}", label_disp, padding)
+    }
+
+    fn labeled_row(
+        &self,
+        directory: &Path,
+        label: &str,
+        long_view: Option<&LongView>,
+        connectors: &str,
+    ) -> String {
+        let mut style = self.entry_style(directory, self.theme.directory);
+        if !is_readable(directory) {
+            style.attributes.set(Attribute::Dim);
+        }
+
+        format!(
+            "{} {}{}{}{}",
+            label,
+            long_view
+                .map(|long_view| long_view.columns(directory))
+                .unwrap_or_default(),
+            connectors,
+            self.icon_prefix(directory, style, "  "),
+            style.apply(format!("{}{}", get_name(directory), link_suffix(directory)))
+        )
+    }
+
+    fn print_select(
+        &mut self,
+        navigator: &Navigator,
+        long_view: Option<&LongView>,
+    ) -> std::io::Result<()> {
+        let dirs = navigator.dirs();
+        let prefix = navigator.prefix.as_str();
+        let keybinds = navigator.keybinds();
+        let paging = navigator.paging();
+        let current_page = navigator.current_page.unwrap_or_default();
+        let cursor_index = navigator.cursor_index.unwrap_or(0);
+
+        let green = self.theme.select;
+        self.screen.write(
+            format!(" Select [{}] ", &prefix)
+                .with(self.theme.header_text)
+                .on(green)
+                .bold(),
+        )?;
+
+        let filtered_dirs = starts_with(dirs, prefix);
+
+        let page = paging.range(current_page, filtered_dirs.len());
+        let (start_idx, end_idx) = (page.start, page.end);
 
         let current_slice = &filtered_dirs[start_idx..end_idx];
         let other_dirs = [&filtered_dirs[..start_idx], &filtered_dirs[end_idx..]].concat();
 
         for (i, directory) in current_slice.iter().enumerate() {
-            let mut number = match self.keybinds.chars().nth(i) {
+            let mut number = match keybinds.chars().nth(i) {
                 Some(c) => c.to_string(),
                 None => String::from("..."),
             }
-            .white();
+            .with(self.theme.key);
 
             if cursor_index == i {
-                number = number.on_white().black();
+                number = number.on(self.theme.key).with(self.theme.header_text);
             }
 
-            let icon: &str = if self.use_icons {
-                &format!("{}  ", icon_for_file(directory))[..]
-            } else {
-                ""
-            };
+            let mut style = ContentStyle::new().with(green);
+            if !is_readable(directory) {
+                style.attributes.set(Attribute::Dim);
+            }
+
+            let name = get_name(directory);
+            let dir_str = format!(
+                "{}{}",
+                name.strip_prefix(prefix).unwrap_or(&name),
+                link_suffix(directory)
+            );
 
-            let dir_str = &get_name(directory)[prefix.len()..];
-            let prefix_str = format!("{}{}", icon, prefix.underlined()).with(green);
             self.screen.write(format!(
-                "[{}] {}{}",
+                "[{}] {}{}{}{}",
                 number,
-                prefix_str,
-                dir_str.with(green)
+                long_view
+                    .map(|long_view| long_view.columns(directory))
+                    .unwrap_or_default(),
+                self.icon_prefix(directory, style, "  "),
+                style.apply(prefix.underlined()),
+                style.apply(dir_str)
             ))?;
         }
 
         let n = filtered_dirs.len();
-        let max_pages = if n == 0 { 0 } else { (n - 1) / 10 };
+        let max_pages = paging.max_page(n);
 
         if max_pages > 0 {
             let other_dirs_info = format!(
                 "Other Directories: {}",
                 other_dirs
                     .iter()
-                    .map(|d| d.file_name().unwrap().to_string_lossy().to_string())
+                    .map(|d| get_name(d))
                     .collect::<Vec<String>>()
                     .join(" ")
             );
@@ -335,7 +1064,7 @@ impl View {
             self.screen.write(other_dirs_info)?;
 
             let page_info = format!(" Page [{}/{}] ", current_page + 1, max_pages + 1)
-                .black()
+                .with(self.theme.header_text)
                 .on(green)
                 .bold();
 
@@ -350,13 +1079,122 @@ impl View {
         Ok(())
     }
 
-    fn color_or_white(&self, color: Color) -> Color {
-        if self.use_colors { color } else { Color::White }
+    // LS_COLORS wins over the theme if it knows the entry.
+    fn entry_style(&self, path: &Path, fallback: Color) -> ContentStyle {
+        self.ls_colors
+            .as_ref()
+            .and_then(|ls_colors| style_for_file(path, ls_colors))
+            .unwrap_or_else(|| ContentStyle::new().with(fallback))
     }
 
-    //fn color_or_black(&self, color: Color) -> Color {
-    //    if self.use_colors { color } else { Color::Black }
-    //}
+    // Glyphs or names of the projects `dir` contains, e.g. Rust and git.
+    fn project_badges(&mut self, dir: &Path) -> String {
+        let kinds = match &mut self.projects {
+            Some(projects) => projects.get(dir).to_vec(),
+            None => return String::new(),
+        };
+
+        kinds
+            .into_iter()
+            .map(|kind| match &self.icons {
+                Some(icons) => {
+                    let (badge, color) = icons.project_badge(kind);
+                    match self.theme.icon_color(color) {
+                        Some(color) => badge.with(color).to_string(),
+                        None => badge.dim().to_string(),
+                    }
+                }
+                None => kind.name().dim().to_string(),
+            })
+            .map(|badge| format!(" {}", badge))
+            .collect()
+    }
+
+    // The icon followed by `separator`, in the color of its file type if the theme allows.
+    fn icon_prefix(&self, path: &Path, row_style: ContentStyle, separator: &str) -> String {
+        let icons = match &self.icons {
+            Some(icons) => icons,
+            None => return String::new(),
+        };
+
+        let (glyph, color) = icons.icon(path);
+        let mut style = row_style;
+        if let Some(color) = self.theme.icon_color(color) {
+            style.foreground_color = Some(color);
+        }
+
+        format!("{}{}", style.apply(glyph), separator)
+    }
+}
+
+// Directories that get a row of their own, the ones shown with metadata in the long view.
+fn visible_dirs(navigator: &Navigator) -> Vec<PathBuf> {
+    match navigator.mode {
+        Mode::Normal | Mode::Filter | Mode::Prompt if navigator.hints => navigator.dirs().to_vec(),
+        Mode::Normal | Mode::Filter | Mode::Prompt => build_char_map(navigator.dirs())
+            .into_values()
+            .filter(|directories| directories.len() == 1)
+            .flatten()
+            .collect(),
+        Mode::Select => {
+            let dirs = starts_with(navigator.dirs(), &navigator.prefix);
+            let page = navigator
+                .paging()
+                .range(navigator.current_page.unwrap_or_default(), dirs.len());
+            dirs[page].to_vec()
+        }
+        Mode::Tree => navigator
+            .tree_rows()
+            .into_iter()
+            .map(|row| row.path)
+            .collect(),
+        Mode::Projects | Mode::Usage => Vec::new(),
+    }
+}
+
+// Row `i` of a pane with `rows` visible rows, the last one says how many were cut off.
+fn pane_row(pane: &[Vec<Segment>], i: usize, rows: usize) -> Vec<Segment> {
+    if pane.len() > rows && i + 1 == rows {
+        let hidden = pane.len() - rows + 1;
+        return vec![(format!("… {} more", hidden), ContentStyle::new().dim())];
+    }
+
+    pane.get(i).cloned().unwrap_or_default()
+}
+
+// Cuts the segments to `width` characters with an ellipsis, or pads them with spaces.
+fn fit_pane(segments: &[Segment], width: usize) -> String {
+    let total: usize = segments.iter().map(|(text, _)| text.chars().count()).sum();
+    let mut room = if total > width {
+        width.saturating_sub(1)
+    } else {
+        width
+    };
+
+    let mut line = String::new();
+    for (text, style) in segments {
+        if room == 0 {
+            break;
+        }
+
+        let mut cut: String = text.chars().take(room).collect();
+        room -= cut.chars().count();
+
+        if total > width && room == 0 {
+            cut.push('…');
+        }
+        line.push_str(&style.apply(cut).to_string());
+    }
+
+    let padding = width.saturating_sub(total.min(width));
+    format!("{}{}", line, " ".repeat(padding))
+}
+
+fn link_suffix(path: &Path) -> String {
+    match symlink_target(path) {
+        Some(target) => format!(" -> {}", escape_os_str(target.as_os_str())),
+        None => String::new(),
+    }
 }
 
 fn display_path(path: &Path) -> String {
@@ -366,8 +1204,8 @@ fn display_path(path: &Path) -> String {
             if stripped.as_os_str().is_empty() {
                 "~".to_string() // just "~" if it's the home directory
             } else {
-                format!("~/{}", stripped.display())
+                format!("~/{}", escape_os_str(stripped.as_os_str()))
             }
         })
-        .unwrap_or_else(|| path.display().to_string())
+        .unwrap_or_else(|| escape_os_str(path.as_os_str()))
 }